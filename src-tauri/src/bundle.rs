@@ -0,0 +1,223 @@
+// Password-encrypted bundles for exports and the secret vault: Argon2id key derivation and XChaCha20-Poly1305
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+pub const BUNDLE_FORMAT: &str = "env-manager/encrypted-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+const MIN_PASSWORD_LENGTH: usize = 8;
+// 导入时接受的派生参数上限，避免恶意文件耗尽内存或 CPU
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+
+// 外层文件为 JSON，只有 ciphertext 是加密的；
+// 除 ciphertext 外的所有字段作为附加数据参与认证，修改任意参数都会导致解密失败
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedBundle {
+    pub format: String,
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub cipher: CipherParams,
+    // 派生密钥的校验值，用于区分密码错误和文件被篡改
+    pub check: String,
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KdfParams {
+    pub algorithm: String,
+    #[serde(rename = "memoryKib")]
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CipherParams {
+    pub algorithm: String,
+    pub nonce: String,
+}
+
+impl EncryptedBundle {
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            self.format,
+            self.version,
+            self.kdf.algorithm,
+            self.kdf.memory_kib,
+            self.kdf.iterations,
+            self.kdf.parallelism,
+            self.kdf.salt,
+            self.cipher.algorithm,
+            self.cipher.nonce
+        )
+        .into_bytes()
+    }
+}
+
+// 派生 64 字节：前 32 字节为加密密钥，后 32 字节的前 16 字节作为校验值
+fn derive_keys(password: &str, kdf: &KdfParams) -> Result<([u8; 32], [u8; 16]), String> {
+    if kdf.algorithm != "argon2id" {
+        return Err(format!("不支持的密钥派生算法: {}", kdf.algorithm));
+    }
+    if kdf.memory_kib > MAX_MEMORY_KIB || kdf.iterations > MAX_ITERATIONS {
+        return Err("加密文件的密钥派生参数超出允许范围".to_string());
+    }
+    let salt = decode_field("salt", &kdf.salt)?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(64))
+        .map_err(|e| format!("无效的密钥派生参数: {}", e))?;
+    let mut output = [0u8; 64];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), &salt, &mut output)
+        .map_err(|e| format!("密钥派生失败: {}", e))?;
+
+    let mut key = [0u8; 32];
+    let mut check = [0u8; 16];
+    key.copy_from_slice(&output[..32]);
+    check.copy_from_slice(&output[32..48]);
+    Ok((key, check))
+}
+
+fn decode_field(field: &str, value: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(value)
+        .map_err(|_| format!("加密文件已损坏: {} 不是有效的 base64", field))
+}
+
+// 用密码加密导出内容，返回外层 JSON
+pub fn encrypt(plaintext: &str, password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!("密码至少需要 {} 个字符", MIN_PASSWORD_LENGTH));
+    }
+
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let defaults = Params::default();
+
+    let mut bundle = EncryptedBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        kdf: KdfParams {
+            algorithm: "argon2id".to_string(),
+            memory_kib: defaults.m_cost(),
+            iterations: defaults.t_cost(),
+            parallelism: defaults.p_cost(),
+            salt: STANDARD.encode(salt),
+        },
+        cipher: CipherParams {
+            algorithm: "xchacha20poly1305".to_string(),
+            nonce: STANDARD.encode(nonce),
+        },
+        check: String::new(),
+        ciphertext: String::new(),
+    };
+
+    let (key, check) = derive_keys(password, &bundle.kdf)?;
+    let aad = bundle.associated_data();
+    let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: &aad })
+        .map_err(|_| "加密失败".to_string())?;
+    bundle.check = STANDARD.encode(check);
+    bundle.ciphertext = STANDARD.encode(ciphertext);
+
+    serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to serialize bundle: {}", e))
+}
+
+// 内容是否为加密导出文件
+pub fn is_bundle(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .map(|value| value.get("format").and_then(|format| format.as_str()) == Some(BUNDLE_FORMAT))
+        .unwrap_or(false)
+}
+
+// 解密导出文件；密码错误和文件被篡改分别给出不同的错误
+pub fn decrypt(content: &str, password: &str) -> Result<String, String> {
+    let bundle: EncryptedBundle = serde_json::from_str(content)
+        .map_err(|e| format!("加密文件已损坏: {}", e))?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err("不是加密导出文件".to_string());
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!("加密文件由更新版本的程序生成（版本 {}），请升级后再导入", bundle.version));
+    }
+    if bundle.cipher.algorithm != "xchacha20poly1305" {
+        return Err(format!("不支持的加密算法: {}", bundle.cipher.algorithm));
+    }
+
+    let (key, check) = derive_keys(password, &bundle.kdf)?;
+    if decode_field("check", &bundle.check)? != check {
+        return Err("密码错误".to_string());
+    }
+
+    let nonce = decode_field("nonce", &bundle.cipher.nonce)?;
+    if nonce.len() != 24 {
+        return Err("加密文件已损坏: nonce 长度不正确".to_string());
+    }
+    let ciphertext = decode_field("ciphertext", &bundle.ciphertext)?;
+    let aad = bundle.associated_data();
+    let plaintext = XChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+        .map_err(|_| "文件已被篡改或损坏，无法通过完整性校验".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "解密后的内容不是有效的 UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse";
+
+    fn tampered(content: &str, edit: impl FnOnce(&mut EncryptedBundle)) -> String {
+        let mut bundle: EncryptedBundle = serde_json::from_str(content).unwrap();
+        edit(&mut bundle);
+        serde_json::to_string(&bundle).unwrap()
+    }
+
+    #[test]
+    fn round_trips_with_the_right_password() {
+        let content = encrypt("{\"variables\":[]}", PASSWORD).unwrap();
+        assert!(is_bundle(&content));
+        assert!(!content.contains("variables"));
+        assert_eq!(decrypt(&content, PASSWORD).unwrap(), "{\"variables\":[]}");
+        assert_eq!(decrypt(&content, "wrong password").unwrap_err(), "密码错误");
+    }
+
+    #[test]
+    fn rejects_short_passwords() {
+        assert!(encrypt("x", "short").is_err());
+    }
+
+    #[test]
+    fn detects_tampering() {
+        let content = encrypt("secret payload", PASSWORD).unwrap();
+        let integrity = "文件已被篡改或损坏，无法通过完整性校验";
+
+        let flipped = tampered(&content, |bundle| {
+            let mut bytes = STANDARD.decode(&bundle.ciphertext).unwrap();
+            bytes[0] ^= 1;
+            bundle.ciphertext = STANDARD.encode(bytes);
+        });
+        assert_eq!(decrypt(&flipped, PASSWORD).unwrap_err(), integrity);
+
+        // nonce 属于附加数据，修改后同样无法通过校验
+        let renonced = tampered(&content, |bundle| {
+            let mut bytes = STANDARD.decode(&bundle.cipher.nonce).unwrap();
+            bytes[0] ^= 1;
+            bundle.cipher.nonce = STANDARD.encode(bytes);
+        });
+        assert_eq!(decrypt(&renonced, PASSWORD).unwrap_err(), integrity);
+
+        let oversized = tampered(&content, |bundle| bundle.kdf.memory_kib = MAX_MEMORY_KIB + 1);
+        assert!(decrypt(&oversized, PASSWORD).is_err());
+
+        assert!(!is_bundle("{\"format\":\"other\"}"));
+        assert!(decrypt("not json", PASSWORD).is_err());
+    }
+}
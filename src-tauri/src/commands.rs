@@ -6,6 +6,25 @@ use std::path::PathBuf;
 use winreg::RegKey;
 use winreg::enums::*;
 
+use crate::bundle;
+use crate::expand;
+use crate::export_schema;
+use crate::exporters;
+use crate::import_plan;
+use crate::importers;
+use crate::limits;
+use crate::metadata;
+use crate::profiles;
+use crate::query;
+use crate::saved_searches;
+use crate::schema;
+use crate::search;
+use crate::secrets;
+use crate::sensitive;
+use crate::templates;
+use crate::validation;
+use crate::vault;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EnvironmentVariable {
     pub id: String,
@@ -15,15 +34,22 @@ pub struct EnvironmentVariable {
     pub var_type: String, // "user" or "system"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub remark: Option<String>,
-    #[serde(rename = "createdAt")]
+    // 不含元数据的导出文件没有时间字段
+    #[serde(rename = "createdAt", default)]
     pub created_at: u64,
-    #[serde(rename = "updatedAt")]
+    #[serde(rename = "updatedAt", default)]
     pub updated_at: u64,
     #[serde(rename = "isValid")]
     pub is_valid: bool,
+    // 敏感变量的值在列表和搜索结果中隐藏，导出时默认脱敏
+    #[serde(default)]
+    pub sensitive: bool,
+    // 注册表类型为 REG_EXPAND_SZ；从 .reg 文件或导出文件导入时以相同类型写入
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expandable: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct SearchQuery {
     #[serde(rename = "nameKeyword")]
     pub name_keyword: Option<String>,
@@ -34,12 +60,37 @@ pub struct SearchQuery {
     pub types: Option<Vec<String>>,
     #[serde(rename = "dateRange")]
     pub date_range: Option<DateRange>,
+    // 名称、值、备注关键字的组合方式，默认要求全部匹配
+    #[serde(rename = "keywordMode", default)]
+    pub keyword_mode: search::KeywordMode,
+    // 字段过滤条件（子串、通配符、正则，可取反），全部满足才算匹配
+    #[serde(default)]
+    pub filters: Vec<search::FieldFilter>,
+    // 只返回验证结果为有效（true）或无效（false）的变量
+    #[serde(rename = "isValid")]
+    pub is_valid: Option<bool>,
+    // 只返回引用了这些变量的变量
+    pub references: Option<Vec<String>>,
+    // 将 PATH 类变量拆分为条目，在结果中返回命中的条目
+    #[serde(rename = "splitPathEntries", default)]
+    pub split_path_entries: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// 搜索参数：结构化查询或查询字符串
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SearchInput {
+    Text(String),
+    Structured(SearchQuery),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DateRange {
     pub start: u64,
     pub end: u64,
+    // 按创建时间、修改时间或任一时间过滤，默认修改时间
+    #[serde(default)]
+    pub field: search::DateField,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,9 +111,70 @@ fn get_current_timestamp() -> u64 {
         .as_secs()
 }
 
-// 获取所有环境变量
+// 广播 WM_SETTINGCHANGE，通知其他程序环境变量已更改
+fn broadcast_environment_change() {
+    unsafe {
+        use winapi::um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, WM_SETTINGCHANGE, SMTO_ABORTIFHUNG};
+        use std::ffi::OsStr;
+        use std::os::windows::ffi::OsStrExt;
+        
+        let env_str: Vec<u16> = OsStr::new("Environment")
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_SETTINGCHANGE,
+            0,
+            env_str.as_ptr() as isize,
+            SMTO_ABORTIFHUNG,
+            5000,
+            std::ptr::null_mut(),
+        );
+    }
+}
+
+// 引用了指定变量的其他变量
+async fn referencing_variables(id: &str) -> Result<Vec<expand::DependencyNode>, String> {
+    let variables = load_environment_variables().await?;
+    let syntax = current_reference_syntax().await;
+    Ok(expand::DependencyGraph::build(&variables, syntax).referenced_by(id))
+}
+
+fn describe_referencing(referencing: &[expand::DependencyNode]) -> String {
+    let names: Vec<String> = referencing.iter()
+        .map(|node| format!("{} ({})", node.name, node.var_type.clone().unwrap_or_default()))
+        .collect();
+    format!("该变量被以下变量引用: {}，请确认后重试", names.join(", "))
+}
+
+// 受保护的系统变量，不能删除，导入时也不会被覆盖
+const PROTECTED_SYSTEM_VARIABLES: [&str; 8] = ["PATH", "PATHEXT", "TEMP", "TMP", "WINDIR", "SYSTEMROOT", "PROGRAMFILES", "PROGRAMFILES(X86)"];
+
+pub fn is_protected_variable(var_type: &str, name: &str) -> bool {
+    var_type == "system" && PROTECTED_SYSTEM_VARIABLES.contains(&name.to_uppercase().as_str())
+}
+
+// 指定作用域（"user" 或 "system"）下已有的变量名
+fn names_in_scope(variables: &[EnvironmentVariable], var_type: &str) -> Vec<String> {
+    let scope = if var_type == "system" { "system" } else { "user" };
+    variables.iter()
+        .filter(|var| var.var_type == scope)
+        .map(|var| var.name.clone())
+        .collect()
+}
+
+// 获取所有环境变量，敏感变量的值已隐藏
 #[tauri::command]
 pub async fn get_environment_variables() -> Result<Vec<EnvironmentVariable>, String> {
+    let mut variables = load_environment_variables().await?;
+    sensitive::mask(&mut variables);
+    Ok(variables)
+}
+
+// 从注册表读取所有环境变量（包含敏感值），供内部使用
+pub async fn load_environment_variables() -> Result<Vec<EnvironmentVariable>, String> {
     let current_time = get_current_timestamp();
     let mut variables = Vec::new();
     
@@ -80,6 +192,8 @@ pub async fn get_environment_variables() -> Result<Vec<EnvironmentVariable>, Str
                     created_at: current_time,
                     updated_at: current_time,
                     is_valid: true,
+                    sensitive: sensitive::is_sensitive_name(&name),
+                    expandable: value.vtype == REG_EXPAND_SZ,
                 });
             }
         }
@@ -100,17 +214,49 @@ pub async fn get_environment_variables() -> Result<Vec<EnvironmentVariable>, Str
                     created_at: current_time,
                     updated_at: current_time,
                     is_valid: true,
+                    sensitive: sensitive::is_sensitive_name(&name),
+                    expandable: value.vtype == REG_EXPAND_SZ,
                 });
             }
         }
     }
     
+    // 填入记录的创建、修改时间、备注和手动标记的敏感状态
+    let _ = metadata::MetadataStore::update(|store| store.apply(&mut variables, current_time));
+    
     Ok(variables)
 }
 
+// 写入成功后更新元数据记录（时间戳、备注），记录失败不影响写入结果
+fn record_metadata_write(id: &str, old_id: Option<&str>, value: &str, remark: Option<String>) {
+    let _ = metadata::MetadataStore::update(|store| {
+        if let Some(old_id) = old_id {
+            store.rename(old_id, id);
+        }
+        store.record_write(id, value, remark, get_current_timestamp());
+        true
+    });
+}
+
+// 删除变量后移除其元数据记录
+fn remove_metadata(id: &str) {
+    let _ = metadata::MetadataStore::update(|store| {
+        store.remove(id);
+        true
+    });
+}
+
 // 添加环境变量
 #[tauri::command]
 pub async fn add_environment_variable(variable: EnvironmentVariable) -> Result<EnvironmentVariable, String> {
+    // 写入前检查名称与值是否合法
+    let current = load_environment_variables().await?;
+    validation::ensure_assignment_allowed(&variable.name, &variable.value, &names_in_scope(&current, &variable.var_type))?;
+    
+    // 写入后不能越过环境块的硬性上限
+    let projected = limits::project_assignment(&current, &variable.var_type, None, &variable.name, &variable.value);
+    limits::ensure_change_within_limits(&current, &projected, current_reference_syntax().await)?;
+    
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     
@@ -137,12 +283,35 @@ pub async fn add_environment_variable(variable: EnvironmentVariable) -> Result<E
         }
     };
     
+    // 来自 REG_EXPAND_SZ 的值以相同类型写入，保留其中 %VAR% 引用的展开
+    let result = result.and_then(|added| {
+        if added.expandable {
+            write_expandable_value(&added.var_type, &added.name, &added.value)?;
+        }
+        Ok(added)
+    });
+    
+    if let Ok(added) = &result {
+        let scope = if added.var_type == "system" { "system" } else { "user" };
+        record_metadata_write(&format!("{}_{}", scope, added.name), None, &added.value, added.remark.clone());
+    }
+    
     result
 }
 
+// 更新的结果；重命名被其他变量引用的变量且未确认（force）时不写入，referencedBy 列出引用它的变量
+#[derive(Debug, Serialize)]
+pub struct UpdateResult {
+    pub updated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<EnvironmentVariable>,
+    #[serde(rename = "referencedBy", skip_serializing_if = "Vec::is_empty")]
+    pub referenced_by: Vec<expand::DependencyNode>,
+}
+
 // 更新环境变量
 #[tauri::command]
-pub async fn update_environment_variable(id: String, variable: EnvironmentVariable) -> Result<EnvironmentVariable, String> {
+pub async fn update_environment_variable(id: String, variable: EnvironmentVariable, force: Option<bool>) -> Result<UpdateResult, String> {
     let parts: Vec<&str> = id.split('_').collect();
     if parts.len() < 2 {
         return Err("无效的ID格式".to_string());
@@ -151,6 +320,51 @@ pub async fn update_environment_variable(id: String, variable: EnvironmentVariab
     let var_type = parts[0];
     let name = &id[parts[0].len() + 1..];
     
+    // 名称发生变化（忽略大小写）时视为重命名，被引用的变量需要确认
+    let is_rename = !variable.name.is_empty() && !variable.name.eq_ignore_ascii_case(name);
+    if is_rename {
+        // 重命名会删除原变量，受保护的系统变量不能重命名
+        if is_protected_variable(var_type, name) {
+            return Err(format!("无法重命名受保护的系统环境变量: {}", name));
+        }
+        // 被其他变量引用时不重命名，由调用方确认后以 force 重试
+        if !force.unwrap_or(false) {
+            let referenced_by = referencing_variables(&id).await?;
+            if !referenced_by.is_empty() {
+                return Ok(UpdateResult { updated: false, variable: None, referenced_by });
+            }
+        }
+    }
+    // 后面会取得 variable 的所有权，这里复制一份名称
+    let target_name = if is_rename { variable.name.clone() } else { name.to_string() };
+    
+    // 写入前检查名称与值是否合法（不与自身比较大小写冲突）
+    let current = load_environment_variables().await?;
+    
+    // 重命名不能覆盖同一作用域中已有的变量
+    if is_rename && names_in_scope(&current, var_type).iter().any(|existing| existing.eq_ignore_ascii_case(&target_name)) {
+        return Err(format!("环境变量 '{}' 已存在，请先删除或换一个名称", target_name));
+    }
+    
+    // 界面只拿到隐藏后的敏感值，未修改时保留原值
+    let mut variable = variable;
+    if variable.value == sensitive::MASK {
+        if let Some(existing) = current.iter().find(|var| var.id == id && var.sensitive) {
+            variable.value = existing.value.clone();
+        }
+    }
+    let existing: Vec<String> = names_in_scope(&current, var_type)
+        .into_iter()
+        .filter(|existing_name| existing_name != name)
+        .collect();
+    validation::ensure_assignment_allowed(&target_name, &variable.value, &existing)?;
+    
+    // 写入后不能越过环境块的硬性上限
+    let projected = limits::project_assignment(&current, var_type, Some(name), &target_name, &variable.value);
+    limits::ensure_change_within_limits(&current, &projected, current_reference_syntax().await)?;
+    
+    let new_id = format!("{}_{}", var_type, target_name);
+    
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     
@@ -160,30 +374,14 @@ pub async fn update_environment_variable(id: String, variable: EnvironmentVariab
             KEY_SET_VALUE,
         );
         if let Ok(env_key) = env_key {
-            env_key.set_value(name, &variable.value)
+            env_key.set_value(&target_name, &variable.value)
                 .map_err(|e| format!("Failed to update system environment variable: {}", e))?;
-            // 通知系统环境变量已更改
-            unsafe {
-                use winapi::um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, WM_SETTINGCHANGE, SMTO_ABORTIFHUNG};
-                use winapi::um::winnt::LPCWSTR;
-                use std::ffi::OsStr;
-                use std::os::windows::ffi::OsStrExt;
-                
-                let env_str: Vec<u16> = OsStr::new("Environment")
-                    .encode_wide()
-                    .chain(std::iter::once(0))
-                    .collect();
-                
-                SendMessageTimeoutW(
-                    HWND_BROADCAST,
-                    WM_SETTINGCHANGE,
-                    0,
-                    env_str.as_ptr() as isize,
-                    SMTO_ABORTIFHUNG,
-                    5000,
-                    std::ptr::null_mut(),
-                );
+            if is_rename {
+                env_key.delete_value(name)
+                    .map_err(|e| format!("Failed to remove renamed system environment variable: {}", e))?;
             }
+            // 通知系统环境变量已更改
+            broadcast_environment_change();
             Ok(variable)
         } else {
             Err("Failed to open system environment key".to_string())
@@ -191,42 +389,47 @@ pub async fn update_environment_variable(id: String, variable: EnvironmentVariab
     } else {
         let env_key = hkcu.open_subkey_with_flags("Environment", KEY_SET_VALUE);
         if let Ok(env_key) = env_key {
-            env_key.set_value(name, &variable.value)
+            env_key.set_value(&target_name, &variable.value)
                 .map_err(|e| format!("Failed to update user environment variable: {}", e))?;
-            // 通知系统环境变量已更改
-            unsafe {
-                use winapi::um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, WM_SETTINGCHANGE, SMTO_ABORTIFHUNG};
-                use winapi::um::winnt::LPCWSTR;
-                use std::ffi::OsStr;
-                use std::os::windows::ffi::OsStrExt;
-                
-                let env_str: Vec<u16> = OsStr::new("Environment")
-                    .encode_wide()
-                    .chain(std::iter::once(0))
-                    .collect();
-                
-                SendMessageTimeoutW(
-                    HWND_BROADCAST,
-                    WM_SETTINGCHANGE,
-                    0,
-                    env_str.as_ptr() as isize,
-                    SMTO_ABORTIFHUNG,
-                    5000,
-                    std::ptr::null_mut(),
-                );
+            if is_rename {
+                env_key.delete_value(name)
+                    .map_err(|e| format!("Failed to remove renamed user environment variable: {}", e))?;
             }
+            // 通知系统环境变量已更改
+            broadcast_environment_change();
             Ok(variable)
         } else {
             Err("Failed to open user environment key".to_string())
         }
     };
     
-    result
+    // 与添加一致，expandable 为 true 时以 REG_EXPAND_SZ 类型重写，保留其中 %VAR% 引用的展开
+    let result = result.and_then(|updated| {
+        if updated.expandable {
+            write_expandable_value(var_type, &target_name, &updated.value)?;
+        }
+        Ok(updated)
+    });
+    
+    if let Ok(updated) = &result {
+        let old_id = if is_rename { Some(id.as_str()) } else { None };
+        record_metadata_write(&new_id, old_id, &updated.value, updated.remark.clone());
+    }
+    
+    result.map(|updated| UpdateResult { updated: true, variable: Some(updated), referenced_by: Vec::new() })
+}
+
+// 删除的结果；变量被其他变量引用且未确认（force）时不删除，referencedBy 列出引用它的变量
+#[derive(Debug, Serialize)]
+pub struct DeleteResult {
+    pub deleted: bool,
+    #[serde(rename = "referencedBy", skip_serializing_if = "Vec::is_empty")]
+    pub referenced_by: Vec<expand::DependencyNode>,
 }
 
 // 删除环境变量
 #[tauri::command]
-pub async fn delete_environment_variable(id: String) -> Result<(), String> {
+pub async fn delete_environment_variable(id: String, force: Option<bool>) -> Result<DeleteResult, String> {
     let parts: Vec<&str> = id.split('_').collect();
     if parts.len() < 2 {
         return Err("无效的ID格式".to_string());
@@ -236,11 +439,18 @@ pub async fn delete_environment_variable(id: String) -> Result<(), String> {
     let name = &id[parts[0].len() + 1..];
     
     // 检查是否为受保护的系统变量
-    let protected_vars = ["PATH", "PATHEXT", "TEMP", "TMP", "WINDIR", "SYSTEMROOT", "PROGRAMFILES", "PROGRAMFILES(X86)"];
-    if var_type == "system" && protected_vars.contains(&name.to_uppercase().as_str()) {
+    if is_protected_variable(var_type, name) {
         return Err(format!("无法删除受保护的系统环境变量: {}", name));
     }
     
+    // 被其他变量引用时不删除，由调用方确认后以 force 重试
+    if !force.unwrap_or(false) {
+        let referenced_by = referencing_variables(&id).await?;
+        if !referenced_by.is_empty() {
+            return Ok(DeleteResult { deleted: false, referenced_by });
+        }
+    }
+    
     let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     
@@ -254,27 +464,8 @@ pub async fn delete_environment_variable(id: String) -> Result<(), String> {
                 match env_key.delete_value(name) {
                     Ok(_) => {
                         // 通知系统环境变量已更改
-                        unsafe {
-                            use winapi::um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, WM_SETTINGCHANGE, SMTO_ABORTIFHUNG};
-                            use winapi::um::winnt::LPCWSTR;
-                            use std::ffi::OsStr;
-                            use std::os::windows::ffi::OsStrExt;
-                            
-                            let env_str: Vec<u16> = OsStr::new("Environment")
-                                .encode_wide()
-                                .chain(std::iter::once(0))
-                                .collect();
-                            
-                            SendMessageTimeoutW(
-                                HWND_BROADCAST,
-                                WM_SETTINGCHANGE,
-                                0,
-                                env_str.as_ptr() as isize,
-                                SMTO_ABORTIFHUNG,
-                                5000,
-                                std::ptr::null_mut(),
-                            );
-                        }
+                        broadcast_environment_change();
+                        remove_metadata(&id);
                         Ok(())
                     },
                     Err(e) => {
@@ -302,27 +493,8 @@ pub async fn delete_environment_variable(id: String) -> Result<(), String> {
                 match env_key.delete_value(name) {
                     Ok(_) => {
                         // 通知系统环境变量已更改
-                        unsafe {
-                            use winapi::um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, WM_SETTINGCHANGE, SMTO_ABORTIFHUNG};
-                            use winapi::um::winnt::LPCWSTR;
-                            use std::ffi::OsStr;
-                            use std::os::windows::ffi::OsStrExt;
-                            
-                            let env_str: Vec<u16> = OsStr::new("Environment")
-                                .encode_wide()
-                                .chain(std::iter::once(0))
-                                .collect();
-                            
-                            SendMessageTimeoutW(
-                                HWND_BROADCAST,
-                                WM_SETTINGCHANGE,
-                                0,
-                                env_str.as_ptr() as isize,
-                                SMTO_ABORTIFHUNG,
-                                5000,
-                                std::ptr::null_mut(),
-                            );
-                        }
+                        broadcast_environment_change();
+                        remove_metadata(&id);
                         Ok(())
                     },
                     Err(e) => {
@@ -338,7 +510,7 @@ pub async fn delete_environment_variable(id: String) -> Result<(), String> {
         }
     };
     
-    result
+    result.map(|_| DeleteResult { deleted: true, referenced_by: Vec::new() })
 }
 
 // 获取所有环境变量的映射表（用于变量引用解析）
@@ -372,43 +544,113 @@ fn get_all_env_vars_map() -> Result<std::collections::HashMap<String, String>, S
     Ok(env_map)
 }
 
-// 展开环境变量引用（如%JAVA_HOME%）
-fn expand_env_references(value: &str, env_map: &std::collections::HashMap<String, String>) -> String {
-    use regex::Regex;
-    
-    let re = Regex::new(r"%([^%]+)%").unwrap();
-    let mut expanded = value.to_string();
+// 当前设置中使用的引用语法
+async fn current_reference_syntax() -> expand::ReferenceSyntax {
+    get_settings().await
+        .map(|settings| settings.reference_syntax)
+        .unwrap_or_default()
+}
+
+// 获取展开后的有效环境（键为大写变量名）
+#[tauri::command]
+pub async fn get_effective_environment(syntax: Option<expand::ReferenceSyntax>) -> Result<std::collections::HashMap<String, String>, String> {
+    let syntax = match syntax {
+        Some(syntax) => syntax,
+        None => current_reference_syntax().await,
+    };
+    let env_map = get_all_env_vars_map()?;
+    let mut expander = expand::Expander::new(&env_map, syntax);
+    let sensitive_names: std::collections::HashSet<String> = load_environment_variables().await?
+        .into_iter()
+        .filter(|var| var.sensitive)
+        .map(|var| var.name.to_uppercase())
+        .collect();
     
-    // 最多展开5层嵌套引用，避免无限循环
-    for _ in 0..5 {
-        let mut changed = false;
-        expanded = re.replace_all(&expanded, |caps: &regex::Captures| {
-            let var_name = caps.get(1).unwrap().as_str().to_uppercase();
-            if let Some(var_value) = env_map.get(&var_name) {
-                changed = true;
-                var_value.clone()
+    Ok(env_map.iter()
+        .map(|(name, value)| {
+            let expanded = if sensitive_names.contains(name) {
+                sensitive::MASK.to_string()
             } else {
-                caps.get(0).unwrap().as_str().to_string() // 保持原样
+                expander.expand_as(name, value).expanded
+            };
+            (name.clone(), expanded)
+        })
+        .collect())
+}
+
+// 单个变量的展开诊断信息
+#[derive(Debug, Serialize)]
+pub struct ExpansionDiagnostics {
+    pub id: String,
+    pub name: String,
+    pub value: String,
+    pub expanded: String,
+    pub references: Vec<String>,
+    pub issues: Vec<expand::ExpansionIssue>,
+    pub messages: Vec<String>,
+}
+
+// 获取变量展开诊断（未定义引用、循环引用）
+#[tauri::command]
+pub async fn get_expansion_diagnostics(ids: Option<Vec<String>>, syntax: Option<expand::ReferenceSyntax>) -> Result<Vec<ExpansionDiagnostics>, String> {
+    let syntax = match syntax {
+        Some(syntax) => syntax,
+        None => current_reference_syntax().await,
+    };
+    let variables = load_environment_variables().await?;
+    let env_map = get_all_env_vars_map()?;
+    let mut expander = expand::Expander::new(&env_map, syntax);
+    
+    let mut diagnostics = Vec::new();
+    for var in variables {
+        if let Some(ids) = &ids {
+            if !ids.contains(&var.id) {
+                continue;
             }
-        }).to_string();
-        
-        if !changed {
-            break;
         }
+        
+        let expansion = expander.expand_as(&var.name, &var.value);
+        let (value, expanded) = if var.sensitive {
+            (sensitive::MASK.to_string(), sensitive::MASK.to_string())
+        } else {
+            (var.value, expansion.expanded)
+        };
+        
+        diagnostics.push(ExpansionDiagnostics {
+            messages: expansion.issues.iter().map(expand::describe_issue).collect(),
+            id: var.id,
+            name: var.name,
+            value,
+            expanded,
+            references: expansion.references,
+            issues: expansion.issues,
+        });
     }
     
-    expanded
+    Ok(diagnostics)
+}
+
+// 获取变量引用依赖图
+#[tauri::command]
+pub async fn get_dependency_graph() -> Result<expand::DependencyGraph, String> {
+    let variables = load_environment_variables().await?;
+    Ok(expand::DependencyGraph::build(&variables, current_reference_syntax().await))
+}
+
+// 获取引用了指定变量的变量列表（删除、重命名前的影响分析）
+#[tauri::command]
+pub async fn get_variable_dependents(id: String) -> Result<Vec<expand::DependencyNode>, String> {
+    let variables = load_environment_variables().await?;
+    Ok(expand::DependencyGraph::build(&variables, current_reference_syntax().await).referenced_by(&id))
 }
 
 // 验证环境变量
 #[tauri::command]
-pub async fn validate_environment_variable(id: String) -> Result<bool, String> {
-    use std::path::Path;
-    
+pub async fn validate_environment_variable(id: String) -> Result<validation::ValidationReport, String> {
     // 解析ID获取变量类型和名称
     let parts: Vec<&str> = id.split('_').collect();
     if parts.len() < 2 {
-        return Ok(false);
+        return Err("无效的ID格式".to_string());
     }
     
     let var_type = parts[0];
@@ -440,169 +682,942 @@ pub async fn validate_environment_variable(id: String) -> Result<bool, String> {
     
     // 获取所有环境变量用于引用解析
     let env_map = get_all_env_vars_map()?;
+    let syntax = current_reference_syntax().await;
     
-    // 根据变量名进行特定验证
-    let is_valid = if name == "PATH" {
-        // 验证PATH变量中的每个路径
-        let paths: Vec<&str> = value.split(';').collect();
-        let mut valid_count = 0;
-        let mut total_count = 0;
-        
-        for path in paths.iter() {
-            // 空路径跳过验证
-            if path.trim().is_empty() {
+    // 敏感变量不按值推测路径，验证结果中也不能出现其值
+    let is_sensitive = load_environment_variables().await?
+        .iter()
+        .find(|var| var.id == id)
+        .map(|var| var.sensitive)
+        .unwrap_or_else(|| sensitive::is_sensitive_name(name));
+    
+    let context = validation::ValidationContext::new(name, &value, &env_map, syntax).with_sensitive(is_sensitive);
+    let mut findings = validation::validate(&context);
+    
+    if is_sensitive {
+        let expanded = expand::expand_value(&value, &env_map, syntax).expanded;
+        sensitive::redact_findings(&mut findings, &value, &expanded);
+    }
+    
+    Ok(validation::ValidationReport::new(id.clone(), name.to_string(), findings))
+}
+
+// 检查变量名和值是否可以写入（添加、导入、重命名前预检）
+#[tauri::command]
+pub async fn check_variable_assignment(name: String, value: String, var_type: String) -> Result<Vec<validation::Finding>, String> {
+    let current = load_environment_variables().await?;
+    Ok(validation::check_assignment(&name, &value, &names_in_scope(&current, &var_type)))
+}
+
+// 检查环境块及各变量的长度限制
+#[tauri::command]
+pub async fn check_size_limits() -> Result<limits::SizeReport, String> {
+    let variables = load_environment_variables().await?;
+    Ok(limits::analyze(&variables, current_reference_syntax().await))
+}
+
+// 批量写入时单个变量的结果
+#[derive(Debug, Serialize, Clone)]
+pub struct ChangeResult {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub var_type: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ChangeResult {
+    fn from_result<T>(variable: &EnvironmentVariable, result: Result<T, String>) -> Self {
+        Self {
+            name: variable.name.clone(),
+            var_type: variable.var_type.clone(),
+            success: result.is_ok(),
+            error: result.err(),
+        }
+    }
+
+    fn from_change(change: &BatchChange, result: &Result<(), String>) -> Self {
+        let (var_type, name) = match change {
+            BatchChange::Set { var_type, name, .. } | BatchChange::Delete { var_type, name } => (var_type, name),
+        };
+        Self {
+            name: name.clone(),
+            var_type: var_type.clone(),
+            success: result.is_ok(),
+            error: result.clone().err(),
+        }
+    }
+}
+
+// 批量修改中的单个操作；expandable 为 true 时以 REG_EXPAND_SZ 类型写入
+#[derive(Debug, Clone)]
+pub enum BatchChange {
+    Set { var_type: String, name: String, value: String, expandable: bool },
+    Delete { var_type: String, name: String },
+}
+
+// 以 REG_EXPAND_SZ 类型重写变量值；add/update 写入的都是 REG_SZ
+fn write_expandable_value(var_type: &str, name: &str, value: &str) -> Result<(), String> {
+    let key = if var_type == "system" {
+        RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey_with_flags(
+            "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment",
+            KEY_SET_VALUE,
+        )
+    } else {
+        RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags("Environment", KEY_SET_VALUE)
+    }
+    .map_err(|e| format!("Failed to open environment key: {}", e))?;
+    
+    let bytes: Vec<u8> = value.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    key.set_raw_value(name, &winreg::RegValue { bytes, vtype: REG_EXPAND_SZ })
+        .map_err(|e| format!("Failed to write expandable value: {}", e))
+}
+
+// 通过 update 命令写入；expandable 为 true 时以 REG_EXPAND_SZ 类型写入，保留原来的注册表类型
+async fn update_with_type(id: String, variable: EnvironmentVariable, expandable: bool) -> Result<EnvironmentVariable, String> {
+    let mut variable = variable;
+    variable.expandable = variable.expandable || expandable;
+    let result = update_environment_variable(id, variable, None).await?;
+    result.variable.ok_or_else(|| describe_referencing(&result.referenced_by))
+}
+
+// 逐个执行批量修改，通过 add/update/delete 命令写入（同样经过名称、值和长度检查）；
+// 单个修改失败不影响其他修改，返回与 changes 一一对应的结果
+pub async fn apply_batch(changes: &[BatchChange]) -> Vec<Result<(), String>> {
+    let mut outcomes = Vec::new();
+    for change in changes {
+        let current = match load_environment_variables().await {
+            Ok(current) => current,
+            Err(e) => {
+                outcomes.push(Err(e));
                 continue;
             }
-            total_count += 1;
-            
-            // 展开环境变量引用
-            let expanded_path = expand_env_references(path.trim(), &env_map);
-            let path_obj = Path::new(&expanded_path);
-            
-            // 检查路径是否存在且可访问
-            if path_obj.exists() {
-                // 进一步检查是否可读取（可访问）
-                if let Ok(metadata) = path_obj.metadata() {
-                    if metadata.is_dir() {
-                        valid_count += 1;
-                    }
-                }
+        };
+        let existing = |var_type: &str, name: &str| current.iter()
+            .find(|var| var.var_type == var_type && var.name.eq_ignore_ascii_case(name))
+            .cloned();
+        
+        let outcome = match change {
+            BatchChange::Set { var_type, name, value, expandable } => {
+                let variable = EnvironmentVariable {
+                    id: format!("{}_{}", var_type, name),
+                    name: name.clone(),
+                    value: value.clone(),
+                    var_type: var_type.clone(),
+                    remark: None,
+                    created_at: 0,
+                    updated_at: 0,
+                    is_valid: true,
+                    sensitive: false,
+                    expandable: false,
+                };
+                let written = match existing(var_type, name) {
+                    Some(var) => update_environment_variable(var.id, variable, Some(true)).await.map(|_| ()),
+                    None => add_environment_variable(variable).await.map(|_| ()),
+                };
+                written.and_then(|_| if *expandable { write_expandable_value(var_type, name, value) } else { Ok(()) })
+            }
+            BatchChange::Delete { var_type, name } => match existing(var_type, name) {
+                Some(var) => delete_environment_variable(var.id, Some(true)).await.map(|_| ()),
+                None => Ok(()),
+            },
+        };
+        outcomes.push(outcome);
+    }
+    broadcast_environment_change();
+    outcomes
+}
+
+// 按团队 schema 文件检查当前变量，并给出修复计划
+#[tauri::command]
+pub async fn check_against_schema(path: String) -> Result<schema::SchemaReport, String> {
+    let env_schema = schema::load_schema(std::path::Path::new(&path))?;
+    let variables = load_environment_variables().await?;
+    let env_map = get_all_env_vars_map()?;
+    Ok(schema::check(&env_schema, &variables, &env_map, current_reference_syntax().await))
+}
+
+// 执行 schema 修复计划，通过 add/update 命令写入
+#[tauri::command]
+pub async fn apply_schema_fixes(plan: Vec<schema::PlannedFix>) -> Result<Vec<ChangeResult>, String> {
+    let expandable = expandable_variable_ids();
+    let mut results = Vec::new();
+    for fix in plan {
+        let result = match (fix.action.as_str(), fix.id.clone()) {
+            ("update", Some(id)) => {
+                let keep_expandable = expandable.contains(&id);
+                update_with_type(id, fix.variable.clone(), keep_expandable).await.map(|_| ())
             }
+            ("add", _) => add_environment_variable(fix.variable.clone()).await.map(|_| ()),
+            (action, _) => Err(format!("未知的修复操作: {}", action)),
+        };
+        results.push(ChangeResult::from_result(&fix.variable, result));
+    }
+    Ok(results)
+}
+
+// 启用或停用配置的结果
+#[derive(Debug, Serialize)]
+pub struct ProfileSwitch {
+    pub name: String,
+    pub results: Vec<ChangeResult>,
+}
+
+// 获取所有配置
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<profiles::Profile>, String> {
+    Ok(profiles::ProfileStore::load()?.profiles)
+}
+
+// 新增或按名称替换配置
+#[tauri::command]
+pub async fn save_profile(profile: profiles::Profile) -> Result<profiles::Profile, String> {
+    let mut store = profiles::ProfileStore::load()?;
+    let saved = store.upsert(profile, get_current_timestamp())?;
+    store.save()?;
+    Ok(saved)
+}
+
+// 删除配置，正在使用的配置不能删除
+#[tauri::command]
+pub async fn delete_profile(name: String) -> Result<(), String> {
+    let mut store = profiles::ProfileStore::load()?;
+    store.remove(&name)?;
+    store.save()
+}
+
+// 获取当前启用的配置及停用时要恢复的值
+#[tauri::command]
+pub async fn get_active_profile() -> Result<Option<profiles::ActiveProfile>, String> {
+    Ok(profiles::ProfileStore::load()?.active)
+}
+
+// 按继承链解析配置，返回每个变量的最终值及来源（按条目修改的变量以当前注册表值为基础）
+#[tauri::command]
+pub async fn resolve_profile(name: String) -> Result<profiles::ResolvedProfile, String> {
+    let store = profiles::ProfileStore::load()?;
+    let current = load_environment_variables().await?;
+    let mut resolved = profiles::resolve(&store, &name, &current)?;
+    
+    // 敏感变量的值同样隐藏
+    for var in resolved.variables.iter_mut() {
+        let sensitive = current.iter()
+            .find(|existing| existing.var_type == var.scope && existing.name.eq_ignore_ascii_case(&var.name))
+            .map(|existing| existing.sensitive)
+            .unwrap_or_else(|| sensitive::is_sensitive_name(&var.name));
+        if sensitive && var.value.is_some() {
+            var.value = Some(sensitive::MASK.to_string());
         }
-        
-        // 如果没有路径或者至少有一半路径有效，则认为有效
-        total_count == 0 || (valid_count as f64 / total_count as f64) >= 0.5
-    } else if name.ends_with("_HOME") || name.ends_with("_DIR") || name.ends_with("_PATH") || value.contains("\\") || value.contains("/") {
-        // 对于可能指向目录或文件的变量进行验证
-        // 展开环境变量引用
-        let expanded_value = expand_env_references(&value, &env_map);
-        let path_obj = Path::new(&expanded_value);
-        path_obj.exists() && {
-            // 检查是否可访问
-            match path_obj.metadata() {
-                Ok(_) => true,
-                Err(_) => false,
+    }
+    Ok(resolved)
+}
+
+// 启用配置（包括继承的配置）：通过批量修改写入，并记录被修改变量原来的值和类型
+#[tauri::command]
+pub async fn activate_profile(name: String) -> Result<ProfileSwitch, String> {
+    let mut store = profiles::ProfileStore::load()?;
+    if let Some(active) = &store.active {
+        return Err(format!("配置 '{}' 正在使用，请先停用", active.name));
+    }
+    let current = load_environment_variables().await?;
+    let resolved = profiles::resolve(&store, &name, &current)?;
+    let (changes, previous): (Vec<_>, Vec<_>) = profiles::plan_activation(&resolved, &current, &expandable_variable_ids())
+        .into_iter()
+        .unzip();
+    if changes.is_empty() {
+        return Err(format!("配置 '{}' 与当前环境相同，没有需要修改的变量", resolved.name));
+    }
+    let outcomes = apply_batch(&changes).await;
+    
+    // 只记录写入成功的修改，停用时只恢复这些变量；全部失败时不启用
+    let recorded: Vec<profiles::PreviousValue> = previous.into_iter()
+        .zip(&outcomes)
+        .filter(|(_, outcome)| outcome.is_ok())
+        .map(|(prev, _)| prev)
+        .collect();
+    if recorded.is_empty() {
+        let errors: Vec<String> = changes.iter()
+            .zip(&outcomes)
+            .map(|(change, outcome)| ChangeResult::from_change(change, outcome))
+            .map(|result| format!("{}: {}", result.name, result.error.unwrap_or_default()))
+            .collect();
+        return Err(format!("配置 '{}' 的修改全部失败，未启用: {}", resolved.name, errors.join("; ")));
+    }
+    store.active = Some(profiles::ActiveProfile {
+        name: resolved.name.clone(),
+        activated_at: get_current_timestamp(),
+        previous: recorded,
+    });
+    store.save()?;
+    
+    Ok(ProfileSwitch {
+        name: resolved.name,
+        results: changes.iter().zip(&outcomes).map(|(change, outcome)| ChangeResult::from_change(change, outcome)).collect(),
+    })
+}
+
+// 停用当前配置，把启用时修改过的变量恢复为原来的值和类型；恢复失败的变量保留记录，可再次停用重试
+#[tauri::command]
+pub async fn deactivate_profile() -> Result<ProfileSwitch, String> {
+    let mut store = profiles::ProfileStore::load()?;
+    let mut active = store.active.take().ok_or_else(|| "当前没有启用的配置".to_string())?;
+    
+    let changes = profiles::plan_restore(&active.previous);
+    let outcomes = apply_batch(&changes).await;
+    
+    // plan_restore 按相反顺序排列
+    let failed: Vec<profiles::PreviousValue> = active.previous.iter()
+        .rev()
+        .zip(&outcomes)
+        .filter(|(_, outcome)| outcome.is_err())
+        .map(|(prev, _)| prev.clone())
+        .rev()
+        .collect();
+    let name = active.name.clone();
+    if !failed.is_empty() {
+        active.previous = failed;
+        store.active = Some(active);
+    }
+    store.save()?;
+    
+    Ok(ProfileSwitch {
+        name,
+        results: changes.iter().zip(&outcomes).map(|(change, outcome)| ChangeResult::from_change(change, outcome)).collect(),
+    })
+}
+
+// 获取所有模板
+#[tauri::command]
+pub async fn list_templates() -> Result<Vec<templates::Template>, String> {
+    Ok(templates::TemplateStore::load()?.templates)
+}
+
+// 新增或按名称替换模板
+#[tauri::command]
+pub async fn save_template(template: templates::Template) -> Result<templates::Template, String> {
+    let mut store = templates::TemplateStore::load()?;
+    let saved = store.upsert(template, get_current_timestamp())?;
+    store.save()?;
+    Ok(saved)
+}
+
+// 删除模板
+#[tauri::command]
+pub async fn delete_template(name: String) -> Result<(), String> {
+    let mut store = templates::TemplateStore::load()?;
+    store.remove(&name)?;
+    store.save()
+}
+
+fn load_template(template: templates::TemplateInput) -> Result<templates::Template, String> {
+    match template {
+        templates::TemplateInput::Name(name) => templates::TemplateStore::load()?.get(&name).cloned(),
+        templates::TemplateInput::Inline(template) => Ok(template),
+    }
+}
+
+// 用参数实例化模板，返回要创建的变量及与现有变量的冲突；不写入任何内容
+#[tauri::command]
+pub async fn instantiate_template(
+    template: templates::TemplateInput,
+    params: Option<std::collections::HashMap<String, String>>,
+) -> Result<templates::TemplatePlan, String> {
+    let template = load_template(template)?;
+    let current = load_environment_variables().await?;
+    let mut plan = templates::instantiate(&template, &params.unwrap_or_default(), &current);
+    for existing in plan.items.iter_mut().filter_map(|item| item.existing.as_mut()) {
+        sensitive::mask(std::slice::from_mut(existing));
+    }
+    Ok(plan)
+}
+
+// 实例化模板并创建其中的新变量；计划中有错误或冲突时不写入
+#[tauri::command]
+pub async fn apply_template(
+    template: templates::TemplateInput,
+    params: Option<std::collections::HashMap<String, String>>,
+) -> Result<Vec<ChangeResult>, String> {
+    let template = load_template(template)?;
+    let current = load_environment_variables().await?;
+    let plan = templates::instantiate(&template, &params.unwrap_or_default(), &current);
+    if !plan.ready {
+        let mut problems = plan.errors.clone();
+        problems.extend(plan.items.iter()
+            .flat_map(|item| &item.findings)
+            .filter(|finding| finding.severity == validation::Severity::Error)
+            .map(|finding| finding.message.clone()));
+        return Err(format!("模板无法实例化: {}", problems.join("; ")));
+    }
+    
+    let mut results = Vec::new();
+    for item in plan.items.into_iter().filter(|item| item.status == templates::PlanStatus::New) {
+        let result = add_environment_variable(item.variable.clone()).await;
+        results.push(ChangeResult::from_result(&item.variable, result));
+    }
+    Ok(results)
+}
+
+// 由变量列表构建引用解析用的映射表，优先级与 get_all_env_vars_map 一致
+fn env_map_from_variables(variables: &[EnvironmentVariable]) -> std::collections::HashMap<String, String> {
+    let mut env_map = std::collections::HashMap::new();
+    for var in variables.iter().filter(|var| var.var_type != "system") {
+        env_map.insert(var.name.to_uppercase(), var.value.clone());
+    }
+    for var in variables.iter().filter(|var| var.var_type == "system") {
+        env_map.insert(var.name.to_uppercase(), var.value.clone());
+    }
+    env_map
+}
+
+// 一次读取所有变量后并发验证，通过 validation-progress 事件推送进度
+#[tauri::command]
+pub async fn validate_all(app: tauri::AppHandle, max_parallelism: Option<usize>) -> Result<validation::ValidationSummary, String> {
+    use tauri::Emitter;
+    
+    let variables = load_environment_variables().await?;
+    let env_map = env_map_from_variables(&variables);
+    let syntax = current_reference_syntax().await;
+    let parallelism = max_parallelism.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8)
+    });
+    
+    let mut summary = tauri::async_runtime::spawn_blocking(move || {
+        let mut summary = validation::validate_all(variables, &env_map, syntax, parallelism, |progress| {
+            let _ = app.emit("validation-progress", progress);
+        });
+        // 隐藏变量值之前，先去掉敏感变量验证结果中出现的值
+        for report in summary.reports.iter_mut() {
+            if let Some(var) = summary.variables.iter().find(|var| var.id == report.id && var.sensitive) {
+                let expanded = expand::expand_value(&var.value, &env_map, syntax).expanded;
+                sensitive::redact_findings(&mut report.findings, &var.value, &expanded);
             }
         }
+        summary
+    })
+    .await
+    .map_err(|e| format!("Validation task failed: {}", e))?;
+    sensitive::mask(&mut summary.variables);
+    Ok(summary)
+}
+
+// 获取内置验证规则列表
+#[tauri::command]
+pub async fn get_validation_rules() -> Result<Vec<validation::Rule>, String> {
+    Ok(validation::rules())
+}
+
+// 搜索环境变量，结果按相关度排序并附带命中位置；revealed 为界面上已显示值的敏感变量 ID，其值参与匹配
+#[tauri::command]
+pub async fn search_environment_variables(query: SearchInput, revealed: Option<Vec<String>>) -> Result<Vec<search::SearchResult>, String> {
+    let query = match query {
+        SearchInput::Text(text) => query::parse(&text).map_err(|e| e.to_string())?,
+        SearchInput::Structured(query) => query,
+    };
+    
+    let all_variables = load_environment_variables().await?;
+    let mut results = run_search(&query, &all_variables, &revealed.unwrap_or_default()).await?;
+    sensitive::mask_results(&mut results);
+    Ok(results)
+}
+
+// 在给定的变量快照上执行查询；敏感变量的值不参与匹配、相关度和 PATH 条目展开（revealed 中的除外），
+// 否则值条件和相关度可以被用来逐字符猜出其内容
+async fn run_search(query: &SearchQuery, all_variables: &[EnvironmentVariable], revealed: &[String]) -> Result<Vec<search::SearchResult>, String> {
+    let syntax = current_reference_syntax().await;
+    let mut visible = all_variables.to_vec();
+    sensitive::mask_except(&mut visible, revealed);
+    let env_map = env_map_from_variables(&visible);
+    
+    // 只有查询包含有效性条件时才执行验证
+    let validity = if query.is_valid.is_some() {
+        let parallelism = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(8);
+        let summary = validation::validate_all(all_variables.to_vec(), &env_map_from_variables(all_variables), syntax, parallelism, |_| {});
+        Some(summary.reports.into_iter().map(|report| (report.id, report.is_valid)).collect())
     } else {
-        // 其他变量默认为有效
-        true
+        None
     };
     
-    Ok(is_valid)
+    search::search(query, &visible, &search::SearchContext { syntax, env_map, validity })
 }
 
-// 搜索环境变量
+// 保存命名搜索，query 可以是结构化查询或查询字符串
 #[tauri::command]
-pub async fn search_environment_variables(query: SearchQuery) -> Result<Vec<EnvironmentVariable>, String> {
-    let all_variables = get_environment_variables().await?;
+pub async fn create_saved_search(name: String, query: SearchInput, smart_filter: Option<bool>) -> Result<saved_searches::SavedSearch, String> {
+    let query = match query {
+        SearchInput::Text(text) => query::parse(&text).map_err(|e| e.to_string())?,
+        SearchInput::Structured(query) => query,
+    };
     
-    let filtered_variables = all_variables.into_iter().filter(|var| {
-        // 名称匹配
-        let name_match = query.name_keyword.as_ref()
-            .map(|keyword| var.name.to_lowercase().contains(&keyword.to_lowercase()))
-            .unwrap_or(true);
-        
-        // 值匹配
-        let value_match = query.value_keyword.as_ref()
-            .map(|keyword| var.value.to_lowercase().contains(&keyword.to_lowercase()))
-            .unwrap_or(true);
-            
-        // 备注匹配
-        let remark_match = query.remark_keyword.as_ref()
-            .map(|keyword| {
-                var.remark.as_ref()
-                    .map(|remark| remark.to_lowercase().contains(&keyword.to_lowercase()))
-                    .unwrap_or(false)
+    let saved = saved_searches::SavedSearch {
+        id: format!("search_{}", chrono::Utc::now().timestamp_millis()),
+        name: name.trim().to_string(),
+        query,
+        smart_filter: smart_filter.unwrap_or(false),
+        created_at: get_current_timestamp(),
+    };
+    
+    let mut store = saved_searches::SavedSearchStore::load()?;
+    store.add(saved.clone())?;
+    store.save()?;
+    Ok(saved)
+}
+
+// 获取所有保存的搜索
+#[tauri::command]
+pub async fn list_saved_searches() -> Result<Vec<saved_searches::SavedSearch>, String> {
+    Ok(saved_searches::SavedSearchStore::load()?.searches)
+}
+
+// 删除保存的搜索
+#[tauri::command]
+pub async fn delete_saved_search(id: String) -> Result<(), String> {
+    let mut store = saved_searches::SavedSearchStore::load()?;
+    store.remove(&id)?;
+    store.save()
+}
+
+// 执行保存的搜索
+#[tauri::command]
+pub async fn run_saved_search(id: String) -> Result<Vec<search::SearchResult>, String> {
+    let store = saved_searches::SavedSearchStore::load()?;
+    let saved = store.get(&id).ok_or_else(|| format!("保存的搜索 '{}' 不存在", id))?;
+    let all_variables = load_environment_variables().await?;
+    let mut results = run_search(&saved.query, &all_variables, &[]).await?;
+    sensitive::mask_results(&mut results);
+    Ok(results)
+}
+
+// 计算所有智能筛选当前的命中数量
+#[tauri::command]
+pub async fn get_smart_filter_counts() -> Result<Vec<saved_searches::SmartFilterCount>, String> {
+    let store = saved_searches::SavedSearchStore::load()?;
+    let all_variables = load_environment_variables().await?;
+    
+    let mut counts = Vec::new();
+    for saved in store.searches.iter().filter(|saved| saved.smart_filter) {
+        let result = run_search(&saved.query, &all_variables, &[]).await;
+        counts.push(saved_searches::SmartFilterCount {
+            id: saved.id.clone(),
+            name: saved.name.clone(),
+            count: result.as_ref().map(|results| results.len()).unwrap_or(0),
+            error: result.err(),
+        });
+    }
+    Ok(counts)
+}
+
+// 解析查询字符串，供界面预览结构化查询或显示语法错误
+#[tauri::command]
+pub async fn parse_search_query(text: String) -> Result<SearchQuery, String> {
+    query::parse(&text).map_err(|e| e.to_string())
+}
+
+// 显示敏感变量的实际值
+#[tauri::command]
+pub async fn reveal_environment_variable(id: String) -> Result<String, String> {
+    load_environment_variables().await?
+        .into_iter()
+        .find(|var| var.id == id)
+        .map(|var| var.value)
+        .ok_or_else(|| format!("环境变量 '{}' 不存在", id))
+}
+
+// 手动标记或取消敏感状态，sensitive 为空时恢复按名称判断
+#[tauri::command]
+pub async fn set_variable_sensitive(id: String, sensitive: Option<bool>) -> Result<EnvironmentVariable, String> {
+    let variables = load_environment_variables().await?;
+    let mut variable = variables.into_iter()
+        .find(|var| var.id == id)
+        .ok_or_else(|| format!("环境变量 '{}' 不存在", id))?;
+    
+    metadata::MetadataStore::update(|store| {
+        store.set_sensitive(&id, sensitive, get_current_timestamp());
+        true
+    })?;
+    
+    variable.sensitive = sensitive.unwrap_or_else(|| sensitive::is_sensitive_name(&variable.name));
+    sensitive::mask(std::slice::from_mut(&mut variable));
+    Ok(variable)
+}
+
+// 用内置规则和 secret-rules.json 中的规则扫描所有变量值，找出疑似明文保存的凭据
+#[tauri::command]
+pub async fn scan_for_secrets() -> Result<secrets::SecretScanReport, String> {
+    let rules = secrets::RulesFile::load()?;
+    let variables = load_environment_variables().await?;
+    secrets::scan(&variables, &rules)
+}
+
+// 获取当前生效的凭据检测规则（内置规则合并规则文件后）
+#[tauri::command]
+pub async fn get_secret_rules() -> Result<Vec<secrets::DetectorRule>, String> {
+    Ok(secrets::effective_rules(&secrets::RulesFile::load()?))
+}
+
+// 获取保险库状态（是否存在、是否已解锁）
+#[tauri::command]
+pub async fn vault_status() -> Result<vault::VaultStatus, String> {
+    vault::status()
+}
+
+// 解锁保险库，第一次使用时用该密码创建
+#[tauri::command]
+pub async fn vault_unlock(password: String) -> Result<vault::VaultStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || vault::unlock(&password))
+        .await
+        .map_err(|e| format!("Vault task failed: {}", e))?
+}
+
+// 锁定保险库，清除内存中的密钥和密码
+#[tauri::command]
+pub async fn vault_lock() -> Result<(), String> {
+    vault::lock()
+}
+
+// 添加或更新保险库中的密钥
+#[tauri::command]
+pub async fn vault_set_secret(name: String, value: String, remark: Option<String>) -> Result<vault::SecretInfo, String> {
+    let now = get_current_timestamp();
+    tauri::async_runtime::spawn_blocking(move || vault::set(&name, &value, None, remark, now))
+        .await
+        .map_err(|e| format!("Vault task failed: {}", e))?
+}
+
+// 删除保险库中的密钥
+#[tauri::command]
+pub async fn vault_delete_secret(name: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || vault::remove(&name).map(|_| ()))
+        .await
+        .map_err(|e| format!("Vault task failed: {}", e))?
+}
+
+// 列出保险库中的密钥（不含值）
+#[tauri::command]
+pub async fn vault_list() -> Result<Vec<vault::SecretInfo>, String> {
+    vault::list()
+}
+
+// 把注册表中的变量移入保险库：先写入保险库，再删除注册表中的值，删除失败时撤销
+#[tauri::command]
+pub async fn move_variable_to_vault(id: String, force: Option<bool>) -> Result<vault::SecretInfo, String> {
+    let variable = load_environment_variables().await?
+        .into_iter()
+        .find(|var| var.id == id)
+        .ok_or_else(|| format!("环境变量 '{}' 不存在", id))?;
+    if vault::get(&variable.name).is_ok() {
+        return Err(format!("保险库中已有密钥 '{}'", variable.name));
+    }
+    
+    let info = vault::set(
+        &variable.name,
+        &variable.value,
+        Some(variable.var_type.clone()),
+        variable.remark.clone(),
+        get_current_timestamp(),
+    )?;
+    match delete_environment_variable(id, force).await {
+        Ok(result) if result.deleted => Ok(info),
+        outcome => {
+            let _ = vault::remove(&variable.name);
+            Err(match outcome {
+                Ok(result) => describe_referencing(&result.referenced_by),
+                Err(e) => e,
             })
-            .unwrap_or(true);
-        
-        // 类型匹配
-        let type_match = query.types.as_ref()
-            .map(|types| types.contains(&var.var_type))
-            .unwrap_or(true);
-        
-        // 关键字匹配（名称或值匹配任一即可）
-        let keyword_match = if query.name_keyword.is_some() || query.value_keyword.is_some() {
-            name_match || value_match
-        } else {
-            true
-        };
-            
-        keyword_match && remark_match && type_match
-    }).collect();
+        }
+    }
+}
+
+// 把保险库中的密钥写回注册表，并从保险库中移除；var_type 为空时使用移入前的作用域
+#[tauri::command]
+pub async fn restore_secret_from_vault(name: String, var_type: Option<String>) -> Result<EnvironmentVariable, String> {
+    let secret = vault::get(&name)?;
+    let scope = var_type.or(secret.scope.clone()).unwrap_or_else(|| "user".to_string());
+    let now = get_current_timestamp();
+    
+    // 不覆盖目标作用域中已有的同名变量
+    let current = load_environment_variables().await?;
+    if names_in_scope(&current, &scope).iter().any(|existing| existing.eq_ignore_ascii_case(&secret.name)) {
+        return Err(format!("环境变量 '{}' 已存在，请先删除或换一个作用域", secret.name));
+    }
+    
+    let mut added = add_environment_variable(EnvironmentVariable {
+        id: format!("{}_{}", scope, secret.name),
+        name: secret.name.clone(),
+        value: secret.value.clone(),
+        var_type: scope,
+        remark: secret.remark.clone(),
+        created_at: now,
+        updated_at: now,
+        is_valid: true,
+        sensitive: true,
+        expandable: false,
+    }).await?;
+    
+    // 移回注册表的变量仍按敏感变量处理；标记失败时撤销写入，密钥保留在保险库中
+    let marked = metadata::MetadataStore::update(|store| {
+        store.set_sensitive(&added.id, Some(true), now);
+        true
+    });
+    if let Err(e) = marked {
+        let _ = delete_environment_variable(added.id.clone(), Some(true)).await;
+        return Err(e);
+    }
+    vault::remove(&secret.name)?;
     
-    Ok(filtered_variables)
+    sensitive::mask(std::slice::from_mut(&mut added));
+    Ok(added)
+}
+
+// 通过应用启动的命令的结果；不等待退出时只有 pid
+#[derive(Debug, Serialize)]
+pub struct LaunchResult {
+    pub pid: u32,
+    #[serde(rename = "exitCode", skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
 }
 
-// 导出环境变量到文件
+// 启动命令，把保险库中的密钥注入子进程环境；密钥不会写入注册表或本程序的环境
 #[tauri::command]
-pub async fn export_environment_variables() -> Result<String, String> {
-    use std::fs::File;
-    use std::io::Write;
+pub async fn run_with_secrets(
+    program: String,
+    args: Option<Vec<String>>,
+    cwd: Option<String>,
+    secrets: Option<Vec<String>>,
+    wait: Option<bool>,
+) -> Result<LaunchResult, String> {
+    let injected = vault::launch_environment(secrets.as_deref())?;
+    
+    let mut command = std::process::Command::new(&program);
+    command.args(args.unwrap_or_default()).envs(injected);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    
+    if !wait.unwrap_or(true) {
+        let child = command.spawn().map_err(|e| format!("无法启动 {}: {}", program, e))?;
+        return Ok(LaunchResult { pid: child.id(), exit_code: None, stdout: None, stderr: None });
+    }
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let child = command
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("无法启动 {}: {}", program, e))?;
+        let pid = child.id();
+        let output = child.wait_with_output().map_err(|e| format!("等待 {} 退出失败: {}", program, e))?;
+        Ok(LaunchResult {
+            pid,
+            exit_code: output.status.code(),
+            stdout: Some(String::from_utf8_lossy(&output.stdout).to_string()),
+            stderr: Some(String::from_utf8_lossy(&output.stderr).to_string()),
+        })
+    })
+    .await
+    .map_err(|e| format!("Launch task failed: {}", e))?
+}
+
+// 注册表中类型为 REG_EXPAND_SZ 的变量 ID，导出 .reg 和更新变量时保留该类型
+fn expandable_variable_ids() -> std::collections::HashSet<String> {
+    let mut ids = std::collections::HashSet::new();
+    let keys = [
+        ("user", RegKey::predef(HKEY_CURRENT_USER).open_subkey("Environment")),
+        ("system", RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey("SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment")),
+    ];
+    for (scope, key) in keys {
+        let Ok(key) = key else { continue };
+        for (name, value) in key.enum_values().flatten() {
+            if value.vtype == REG_EXPAND_SZ {
+                ids.insert(format!("{}_{}", scope, name));
+            }
+        }
+    }
+    ids
+}
+
+// 导出环境变量；可按 ID、查询和作用域筛选，写入指定文件、桌面或只返回内容
+#[tauri::command]
+pub async fn export_environment_variables(options: Option<exporters::ExportOptions>) -> Result<exporters::ExportOutput, String> {
     use chrono::Utc;
     use dirs::desktop_dir;
     
-    // 获取所有环境变量
-    let variables = get_environment_variables().await?;
+    let options = options.unwrap_or_default();
+    let format = options.format;
     
-    // 创建导出数据结构
-    let export_data = serde_json::json!({
-        "version": "1.0",
-        "exportedAt": Utc::now().to_rfc3339(),
-        "variables": variables
-    });
+    let all_variables = load_environment_variables().await?;
+    let mut variables = all_variables.clone();
     
-    // 生成文件路径 (使用桌面路径)
-    let desktop_path = desktop_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-    let file_name = format!("env-export-{}.json", Utc::now().format("%Y%m%d-%H%M%S"));
-    let file_path = desktop_path.join(&file_name);
+    if let Some(ids) = &options.ids {
+        let unknown: Vec<&str> = ids.iter()
+            .filter(|id| !all_variables.iter().any(|var| &var.id == *id))
+            .map(|id| id.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!("以下变量不存在: {}", unknown.join(", ")));
+        }
+        variables.retain(|var| ids.contains(&var.id));
+    }
     
-    // 写入文件
-    let mut file = File::create(&file_path)
-        .map_err(|e| format!("Failed to create export file: {}", e))?;
-        
-    file.write_all(serde_json::to_string_pretty(&export_data)
-        .map_err(|e| format!("Failed to serialize data: {}", e))?.as_bytes())
+    if let Some(query) = options.query {
+        let query = match query {
+            SearchInput::Text(text) => query::parse(&text).map_err(|e| e.to_string())?,
+            SearchInput::Structured(query) => query,
+        };
+        let matched: std::collections::HashSet<String> = run_search(&query, &all_variables, &[]).await?
+            .into_iter()
+            .map(|result| result.variable.id)
+            .collect();
+        variables.retain(|var| matched.contains(&var.id));
+    }
+    
+    if let Some(scope) = &options.scope {
+        if scope != "user" && scope != "system" {
+            return Err(format!("未知的作用域 '{}'，应为 user 或 system", scope));
+        }
+        variables.retain(|var| &var.var_type == scope);
+    }
+    
+    if options.password.is_some() && format != exporters::ExportFormat::Json {
+        return Err("加密导出只支持 JSON 格式".to_string());
+    }
+    
+    // 敏感变量默认脱敏，只有明确要求时才导出其值
+    let redacted: std::collections::HashSet<String> = if options.include_secrets {
+        std::collections::HashSet::new()
+    } else {
+        variables.iter().filter(|var| var.sensitive).map(|var| var.id.clone()).collect()
+    };
+    
+    let expandable = expandable_variable_ids();
+    let input = exporters::ExportInput {
+        variables: &variables,
+        expandable: &expandable,
+        redacted: &redacted,
+        exported_at: Utc::now().to_rfc3339(),
+        include_metadata: options.include_metadata,
+    };
+    let mut content = exporters::render(format, &input)?;
+    let warnings = exporters::warnings(format, &input);
+    if let Some(password) = &options.password {
+        content = bundle::encrypt(&content, password)?;
+    }
+    
+    if options.in_memory {
+        return Ok(exporters::ExportOutput {
+            format,
+            count: variables.len(),
+            path: None,
+            content: Some(content),
+            warnings,
+        });
+    }
+    
+    // 未指定输出文件时写入桌面
+    let file_path = match &options.output_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let desktop_path = desktop_dir().unwrap_or_else(|| PathBuf::from("."));
+            let extension = if options.password.is_some() { "enc.json" } else { format.extension() };
+            let file_name = format!("env-export-{}.{}", Utc::now().format("%Y%m%d-%H%M%S"), extension);
+            desktop_path.join(file_name)
+        }
+    };
+    
+    fs::write(&file_path, exporters::encode(format, &content))
         .map_err(|e| format!("Failed to write to file: {}", e))?;
     
-    // 返回文件路径
-    Ok(file_path.to_string_lossy().to_string())
+    Ok(exporters::ExportOutput {
+        format,
+        count: variables.len(),
+        path: Some(file_path.to_string_lossy().to_string()),
+        content: None,
+        warnings,
+    })
+}
+
+// 获取当前导出格式的 JSON Schema
+#[tauri::command]
+pub async fn get_export_schema() -> Result<serde_json::Value, String> {
+    Ok(export_schema::json_schema())
+}
+
+// 解析导入文件，返回识别的格式、变量和逐行诊断；format 为空时自动识别
+#[tauri::command]
+pub async fn parse_import_file(file_path: String, format: Option<importers::ImportFormat>, password: Option<String>) -> Result<importers::ParsedImport, String> {
+    importers::parse_file(std::path::Path::new(&file_path), format, password.as_deref())
 }
 
-// 从文件导入环境变量
+// 解析导入文件并与现有变量比较，标记每个变量是新增、相同、冲突还是受保护
 #[tauri::command]
-pub async fn import_environment_variables(file_path: String) -> Result<Vec<EnvironmentVariable>, String> {
-    use std::fs::File;
-    use std::io::Read;
+pub async fn preview_import(file_path: String, format: Option<importers::ImportFormat>, password: Option<String>) -> Result<import_plan::ImportPreview, String> {
+    let parsed = importers::parse_file(std::path::Path::new(&file_path), format, password.as_deref())?;
+    let current = load_environment_variables().await?;
+    let env_map = env_map_from_variables(&current);
+    let mut items = import_plan::preview(&parsed.variables, &current, &env_map, current_reference_syntax().await);
+    for existing in items.iter_mut().filter_map(|item| item.existing.as_mut()) {
+        sensitive::mask(std::slice::from_mut(existing));
+    }
     
-    // 读取文件
-    let mut file = File::open(&file_path)
-        .map_err(|e| format!("Failed to open file: {}", e))?;
-        
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    
-    // 解析JSON
-    let import_data: serde_json::Value = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    
-    // 获取变量列表
-    let variables: Vec<EnvironmentVariable> = serde_json::from_value(
-        import_data["variables"].clone()
-    ).map_err(|e| format!("Failed to extract variables: {}", e))?;
-    
-    // 处理导入的变量（添加到系统中）
-    let mut imported_variables = Vec::new();
-    for variable in variables {
-        match add_environment_variable(variable.clone()).await {
-            Ok(imported) => imported_variables.push(imported),
-            Err(e) => eprintln!("Failed to import variable {}: {}", variable.name, e),
-        }
+    Ok(import_plan::ImportPreview {
+        format: parsed.format,
+        diagnostics: parsed.diagnostics,
+        items,
+    })
+}
+
+// 从文件导入环境变量，按 options 中的策略处理与现有变量的冲突（默认跳过）
+#[tauri::command]
+pub async fn import_environment_variables(
+    file_path: String,
+    format: Option<importers::ImportFormat>,
+    password: Option<String>,
+    options: Option<import_plan::ImportOptions>,
+) -> Result<import_plan::ImportReport, String> {
+    let parsed = importers::parse_file(std::path::Path::new(&file_path), format, password.as_deref())?;
+    
+    // 没有可导入的变量时返回解析错误
+    if parsed.variables.is_empty() && parsed.has_errors() {
+        let errors: Vec<String> = parsed.diagnostics.iter()
+            .filter(|d| d.severity == validation::Severity::Error)
+            .map(|d| format!("第 {} 行: {}", d.line, d.message))
+            .collect();
+        return Err(errors.join("; "));
+    }
+    
+    let current = load_environment_variables().await?;
+    let env_map = env_map_from_variables(&current);
+    let items = import_plan::preview(&parsed.variables, &current, &env_map, current_reference_syntax().await);
+    let plan = import_plan::plan(items, &current, &options.unwrap_or_default());
+    
+    // 逐个写入，单个变量失败不影响其他变量；更新时保留原来的注册表类型（如 PATH 的 REG_EXPAND_SZ）
+    let expandable = expandable_variable_ids();
+    let mut results = Vec::new();
+    for planned in &plan {
+        let outcome = match &planned.action {
+            import_plan::Action::Add(variable) => add_environment_variable(variable.clone()).await
+                .map(|added| Some(added.name)),
+            import_plan::Action::Update { id, variable } => update_with_type(id.clone(), variable.clone(), variable.expandable || expandable.contains(id)).await
+                .map(|updated| Some(updated.name)),
+            import_plan::Action::Skip(_) => Ok(None),
+            import_plan::Action::Reject(reason) => Err(reason.clone()),
+        };
+        results.push(import_plan::ImportResult::new(planned, outcome));
     }
     
-    Ok(imported_variables)
+    Ok(import_plan::ImportReport {
+        format: parsed.format,
+        diagnostics: parsed.diagnostics,
+        results,
+    })
 }
 
 // 检查更新
@@ -661,6 +1676,9 @@ pub struct AppSettings {
     pub auto_start: bool,
     #[serde(rename = "showHotkey")]
     pub show_hotkey: String,
+    // 变量引用语法：windows（%VAR%）、posix（$VAR、${VAR}）或 both
+    #[serde(rename = "referenceSyntax", default)]
+    pub reference_syntax: expand::ReferenceSyntax,
 }
 
 impl Default for AppSettings {
@@ -669,12 +1687,13 @@ impl Default for AppSettings {
             theme: "system".to_string(),
             auto_start: false,
             show_hotkey: "Ctrl+Shift+E".to_string(),
+            reference_syntax: expand::ReferenceSyntax::default(),
         }
     }
 }
 
-// 获取设置文件路径
-fn get_settings_path() -> Result<PathBuf, String> {
+// 获取设置目录（不存在时创建）
+pub fn get_settings_dir() -> Result<PathBuf, String> {
     let home_dir = std::env::var("APPDATA")
         .or_else(|_| std::env::var("HOME"))
         .map_err(|_| "Failed to get home directory")?;
@@ -686,7 +1705,12 @@ fn get_settings_path() -> Result<PathBuf, String> {
             .map_err(|e| format!("Failed to create settings directory: {}", e))?;
     }
     
-    Ok(settings_dir.join("settings.json"))
+    Ok(settings_dir)
+}
+
+// 获取设置文件路径
+fn get_settings_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("settings.json"))
 }
 
 // 获取设置
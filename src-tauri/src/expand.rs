@@ -0,0 +1,450 @@
+// Reference expansion engine for %VAR% and $VAR style environment values
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::commands::EnvironmentVariable;
+
+// 展开过程中发现的问题
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum ExpansionIssue {
+    // 引用了不存在的变量
+    #[serde(rename = "undefined")]
+    Undefined { name: String },
+    // 循环引用，chain 为参与循环的变量（首尾相同）
+    #[serde(rename = "cycle")]
+    Cycle { chain: Vec<String> },
+}
+
+// 单个值的展开结果
+#[derive(Debug, Serialize, Clone)]
+pub struct Expansion {
+    pub expanded: String,
+    // 值中直接引用的变量名（大写，去重，保持出现顺序）
+    pub references: Vec<String>,
+    pub issues: Vec<ExpansionIssue>,
+}
+
+// 支持的引用语法：Windows 的 %VAR%，POSIX 的 $VAR、${VAR}、${VAR:-default}
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ReferenceSyntax {
+    #[default]
+    #[serde(rename = "windows")]
+    Windows,
+    #[serde(rename = "posix")]
+    Posix,
+    #[serde(rename = "both")]
+    Both,
+}
+
+impl ReferenceSyntax {
+    // 每种语法的正则只编译一次
+    fn regex(self) -> &'static Regex {
+        static WINDOWS: OnceLock<Regex> = OnceLock::new();
+        static POSIX: OnceLock<Regex> = OnceLock::new();
+        static BOTH: OnceLock<Regex> = OnceLock::new();
+
+        let percent = r"%(?P<percent>[^%]+)%";
+        let posix = r"\$\{(?P<braced>[A-Za-z_][A-Za-z0-9_]*)(?::-(?P<default>[^}]*))?\}|\$(?P<bare>[A-Za-z_][A-Za-z0-9_]*)";
+        match self {
+            ReferenceSyntax::Windows => WINDOWS.get_or_init(|| Regex::new(percent).unwrap()),
+            ReferenceSyntax::Posix => POSIX.get_or_init(|| Regex::new(posix).unwrap()),
+            ReferenceSyntax::Both => BOTH.get_or_init(|| Regex::new(&format!("{}|{}", percent, posix)).unwrap()),
+        }
+    }
+}
+
+// 值中的一次变量引用及其在原始字符串中的字节范围
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub name: String,
+    // ${VAR:-default} 中的默认值，变量未定义或为空时使用
+    pub default: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+// 按指定语法解析值中所有的变量引用
+pub fn parse_references(value: &str, syntax: ReferenceSyntax) -> Vec<Reference> {
+    syntax
+        .regex()
+        .captures_iter(value)
+        .map(|caps| {
+            let whole = caps.get(0).unwrap();
+            let name = caps.name("percent")
+                .or_else(|| caps.name("braced"))
+                .or_else(|| caps.name("bare"))
+                .unwrap()
+                .as_str();
+            Reference {
+                name: name.to_uppercase(),
+                default: caps.name("default").map(|m| m.as_str().to_string()),
+                start: whole.start(),
+                end: whole.end(),
+            }
+        })
+        .collect()
+}
+
+// 值中直接引用的变量名（大写，去重）
+pub fn referenced_names(value: &str, syntax: ReferenceSyntax) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for reference in parse_references(value, syntax) {
+        if !names.contains(&reference.name) {
+            names.push(reference.name);
+        }
+    }
+    names
+}
+
+// 基于引用图的展开器，env_map 的键为大写变量名
+pub struct Expander<'a> {
+    env_map: &'a HashMap<String, String>,
+    syntax: ReferenceSyntax,
+    // 已完整展开（不涉及循环）的变量缓存
+    resolved: HashMap<String, (String, Vec<ExpansionIssue>)>,
+}
+
+impl<'a> Expander<'a> {
+    pub fn new(env_map: &'a HashMap<String, String>, syntax: ReferenceSyntax) -> Self {
+        Self {
+            env_map,
+            syntax,
+            resolved: HashMap::new(),
+        }
+    }
+
+    // 展开任意值
+    pub fn expand(&mut self, value: &str) -> Expansion {
+        let mut stack = Vec::new();
+        let (expanded, issues) = self.expand_with_stack(value, &mut stack);
+        Expansion {
+            expanded,
+            references: referenced_names(value, self.syntax),
+            issues,
+        }
+    }
+
+    // 以变量 name 的身份展开 value，变量自身也参与循环检测（如 A=%B%、B=%A%）
+    pub fn expand_as(&mut self, name: &str, value: &str) -> Expansion {
+        let mut stack = vec![name.to_uppercase()];
+        let (expanded, issues) = self.expand_with_stack(value, &mut stack);
+        Expansion {
+            expanded,
+            references: referenced_names(value, self.syntax),
+            issues,
+        }
+    }
+
+    fn expand_with_stack(&mut self, value: &str, stack: &mut Vec<String>) -> (String, Vec<ExpansionIssue>) {
+        let mut expanded = String::with_capacity(value.len());
+        let mut issues = Vec::new();
+        let mut last = 0;
+
+        for reference in parse_references(value, self.syntax) {
+            expanded.push_str(&value[last..reference.start]);
+            last = reference.end;
+            let original = &value[reference.start..reference.end];
+
+            match self.resolve(&reference.name, stack) {
+                Some((text, nested)) if !(text.is_empty() && reference.default.is_some()) => {
+                    expanded.push_str(&text);
+                    merge_issues(&mut issues, nested);
+                }
+                resolved => {
+                    let in_cycle = resolved.is_none() && stack.iter().any(|n| n == &reference.name);
+                    if let (Some(default), false) = (&reference.default, in_cycle) {
+                        // ${VAR:-default}：变量未定义或为空时展开默认值
+                        let (text, nested) = self.expand_with_stack(default, stack);
+                        expanded.push_str(&text);
+                        merge_issues(&mut issues, nested);
+                        continue;
+                    }
+
+                    // 未能解析的引用保持原样
+                    expanded.push_str(original);
+                    let issue = if let Some(pos) = stack.iter().position(|n| n == &reference.name) {
+                        let mut chain = stack[pos..].to_vec();
+                        chain.push(reference.name.clone());
+                        ExpansionIssue::Cycle { chain }
+                    } else {
+                        ExpansionIssue::Undefined { name: reference.name.clone() }
+                    };
+                    merge_issues(&mut issues, vec![issue]);
+                }
+            }
+        }
+        expanded.push_str(&value[last..]);
+
+        (expanded, issues)
+    }
+
+    // 返回 None 表示变量未定义或处于当前引用链中（循环）
+    fn resolve(&mut self, name: &str, stack: &mut Vec<String>) -> Option<(String, Vec<ExpansionIssue>)> {
+        if stack.iter().any(|n| n == name) {
+            return None;
+        }
+        if let Some(cached) = self.resolved.get(name) {
+            return Some(cached.clone());
+        }
+        let value = self.env_map.get(name)?.clone();
+
+        stack.push(name.to_string());
+        let (expanded, issues) = self.expand_with_stack(&value, stack);
+        stack.pop();
+
+        // 含循环的结果依赖于当前引用链，不能缓存
+        if !issues.iter().any(|issue| matches!(issue, ExpansionIssue::Cycle { .. })) {
+            self.resolved.insert(name.to_string(), (expanded.clone(), issues.clone()));
+        }
+        Some((expanded, issues))
+    }
+}
+
+fn merge_issues(target: &mut Vec<ExpansionIssue>, incoming: Vec<ExpansionIssue>) {
+    for issue in incoming {
+        if !target.contains(&issue) {
+            target.push(issue);
+        }
+    }
+}
+
+// 便捷函数：展开单个值
+pub fn expand_value(value: &str, env_map: &HashMap<String, String>, syntax: ReferenceSyntax) -> Expansion {
+    Expander::new(env_map, syntax).expand(value)
+}
+
+// 将问题转换为可读的说明
+pub fn describe_issue(issue: &ExpansionIssue) -> String {
+    match issue {
+        ExpansionIssue::Undefined { name } => format!("引用的变量 {} 未定义", name),
+        ExpansionIssue::Cycle { chain } => format!("检测到循环引用: {}", chain.join(" -> ")),
+    }
+}
+
+// 依赖图中的节点，未定义的引用也会作为节点出现
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyNode {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub var_type: Option<String>,
+    pub defined: bool,
+}
+
+// from 的值中引用了 to
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DependencyGraph {
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    // 根据变量列表构建引用图，同名变量按系统变量优先解析（与 get_all_env_vars_map 一致）
+    pub fn build(variables: &[EnvironmentVariable], syntax: ReferenceSyntax) -> Self {
+        let mut nodes: Vec<DependencyNode> = variables
+            .iter()
+            .map(|var| DependencyNode {
+                id: var.id.clone(),
+                name: var.name.clone(),
+                var_type: Some(var.var_type.clone()),
+                defined: true,
+            })
+            .collect();
+
+        let mut targets: HashMap<String, String> = HashMap::new();
+        for var in variables.iter().filter(|v| v.var_type != "system") {
+            targets.insert(var.name.to_uppercase(), var.id.clone());
+        }
+        for var in variables.iter().filter(|v| v.var_type == "system") {
+            targets.insert(var.name.to_uppercase(), var.id.clone());
+        }
+
+        let mut edges = Vec::new();
+        for var in variables {
+            for name in referenced_names(&var.value, syntax) {
+                let to = match targets.get(&name) {
+                    Some(id) => id.clone(),
+                    None => {
+                        let id = format!("undefined_{}", name);
+                        if !nodes.iter().any(|node| node.id == id) {
+                            nodes.push(DependencyNode {
+                                id: id.clone(),
+                                name: name.clone(),
+                                var_type: None,
+                                defined: false,
+                            });
+                        }
+                        id
+                    }
+                };
+                edges.push(DependencyEdge { from: var.id.clone(), to });
+            }
+        }
+
+        Self { nodes, edges }
+    }
+
+    // 直接引用了指定变量的节点
+    pub fn referenced_by(&self, id: &str) -> Vec<DependencyNode> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.to == id && edge.from != id)
+            .filter_map(|edge| self.nodes.iter().find(|node| node.id == edge.from))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_uppercase(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn parses_references_with_byte_ranges() {
+        let value = r"%java_home%\bin;%Path%";
+        let references = parse_references(value, ReferenceSyntax::Windows);
+        let parsed: Vec<(&str, &str)> = references.iter().map(|r| (r.name.as_str(), &value[r.start..r.end])).collect();
+        assert_eq!(parsed, vec![("JAVA_HOME", "%java_home%"), ("PATH", "%Path%")]);
+        assert_eq!(referenced_names("%A%;%a%;%B%", ReferenceSyntax::Windows), vec!["A", "B"]);
+    }
+
+    #[test]
+    fn expands_nested_references() {
+        let env_map = env(&[("JAVA_HOME", r"%TOOLS%\jdk"), ("TOOLS", r"C:\tools")]);
+        let expansion = expand_value(r"%JAVA_HOME%\bin", &env_map, ReferenceSyntax::Windows);
+        assert_eq!(expansion.expanded, r"C:\tools\jdk\bin");
+        assert_eq!(expansion.references, vec!["JAVA_HOME"]);
+        assert!(expansion.issues.is_empty());
+    }
+
+    #[test]
+    fn keeps_undefined_references_and_reports_them() {
+        let expansion = expand_value(r"%MISSING%\bin;%MISSING%", &env(&[]), ReferenceSyntax::Windows);
+        assert_eq!(expansion.expanded, r"%MISSING%\bin;%MISSING%");
+        assert_eq!(expansion.issues, vec![ExpansionIssue::Undefined { name: "MISSING".to_string() }]);
+        assert_eq!(describe_issue(&expansion.issues[0]), "引用的变量 MISSING 未定义");
+    }
+
+    #[test]
+    fn detects_cycles_including_the_variable_itself() {
+        let env_map = env(&[("A", "%B%"), ("B", "%C%"), ("C", "%A%")]);
+        let expansion = Expander::new(&env_map, ReferenceSyntax::Windows).expand_as("a", "%B%");
+        assert_eq!(
+            expansion.issues,
+            vec![ExpansionIssue::Cycle { chain: vec!["A".into(), "B".into(), "C".into(), "A".into()] }]
+        );
+        assert_eq!(expansion.expanded, "%A%");
+        assert_eq!(describe_issue(&expansion.issues[0]), "检测到循环引用: A -> B -> C -> A");
+
+        let expansion = Expander::new(&env(&[("SELF", "x;%SELF%")]), ReferenceSyntax::Windows).expand_as("SELF", "x;%SELF%");
+        assert_eq!(expansion.issues, vec![ExpansionIssue::Cycle { chain: vec!["SELF".into(), "SELF".into()] }]);
+    }
+
+    #[test]
+    fn does_not_cache_results_that_depend_on_the_reference_chain() {
+        let env_map = env(&[("A", "%B%"), ("B", "b%A%")]);
+        let mut expander = Expander::new(&env_map, ReferenceSyntax::Windows);
+        // 以 A 的身份展开时 B 处于循环中；之后单独展开 B 不能复用该结果
+        assert!(!expander.expand_as("A", "%B%").issues.is_empty());
+        let expansion = expander.expand("%B%");
+        assert_eq!(expansion.expanded, "b%B%");
+        assert_eq!(
+            expansion.issues,
+            vec![ExpansionIssue::Cycle { chain: vec!["B".into(), "A".into(), "B".into()] }]
+        );
+    }
+
+    #[test]
+    fn builds_dependency_graph_with_undefined_nodes() {
+        let variables = vec![
+            var("user", "JAVA_HOME", r"C:\jdk"),
+            var("system", "JAVA_HOME", r"D:\jdk"),
+            var("user", "Path", r"%JAVA_HOME%\bin;%MAVEN_HOME%\bin;%Path%"),
+        ];
+        let graph = DependencyGraph::build(&variables, ReferenceSyntax::Windows);
+        let edges: Vec<(&str, &str)> = graph.edges.iter().map(|e| (e.from.as_str(), e.to.as_str())).collect();
+        // 同名变量按系统变量解析
+        assert_eq!(
+            edges,
+            vec![
+                ("user_Path", "system_JAVA_HOME"),
+                ("user_Path", "undefined_MAVEN_HOME"),
+                ("user_Path", "user_Path"),
+            ]
+        );
+        assert!(graph.nodes.iter().any(|node| node.id == "undefined_MAVEN_HOME" && !node.defined && node.var_type.is_none()));
+
+        let referencing: Vec<String> = graph.referenced_by("system_JAVA_HOME").into_iter().map(|node| node.id).collect();
+        assert_eq!(referencing, vec!["user_Path"]);
+        // 引用自身不算被其他变量引用
+        assert!(graph.referenced_by("user_Path").is_empty());
+    }
+
+    #[test]
+    fn parses_posix_references() {
+        let value = "$HOME/bin:${GOPATH}/bin:${EDITOR:-vim}:$1:$$";
+        let parsed: Vec<(String, Option<String>, &str)> = parse_references(value, ReferenceSyntax::Posix)
+            .into_iter()
+            .map(|r| (r.name, r.default, &value[r.start..r.end]))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("HOME".to_string(), None, "$HOME"),
+                ("GOPATH".to_string(), None, "${GOPATH}"),
+                ("EDITOR".to_string(), Some("vim".to_string()), "${EDITOR:-vim}"),
+            ]
+        );
+        // 各语法只识别自己的写法
+        assert!(parse_references("%HOME%", ReferenceSyntax::Posix).is_empty());
+        assert!(parse_references("$HOME", ReferenceSyntax::Windows).is_empty());
+    }
+
+    #[test]
+    fn expands_posix_defaults_when_undefined_or_empty() {
+        let env_map = env(&[("EMPTY", ""), ("SET", "value"), ("FALLBACK", "fb")]);
+        let expansion = expand_value("${MISSING:-$FALLBACK}|${EMPTY:-d}|${SET:-d}|${EMPTY}", &env_map, ReferenceSyntax::Posix);
+        assert_eq!(expansion.expanded, "fb|d|value|");
+        assert!(expansion.issues.is_empty());
+
+        let expansion = expand_value("$MISSING/bin", &env_map, ReferenceSyntax::Posix);
+        assert_eq!(expansion.expanded, "$MISSING/bin");
+        assert_eq!(expansion.issues, vec![ExpansionIssue::Undefined { name: "MISSING".to_string() }]);
+    }
+
+    #[test]
+    fn mixes_percent_and_posix_references() {
+        let env_map = env(&[("ROOT", r"C:\tools"), ("JDK", r"%ROOT%\jdk"), ("BIN", r"${JDK}\bin")]);
+        let expansion = expand_value("%BIN%;$ROOT", &env_map, ReferenceSyntax::Both);
+        assert_eq!(expansion.expanded, r"C:\tools\jdk\bin;C:\tools");
+        assert_eq!(expansion.references, vec!["BIN", "ROOT"]);
+
+        // 只按 Windows 语法展开时 POSIX 写法保持原样
+        let expansion = expand_value("%BIN%", &env_map, ReferenceSyntax::Windows);
+        assert_eq!(expansion.expanded, r"${JDK}\bin");
+    }
+
+    #[test]
+    fn reports_cycles_through_posix_defaults() {
+        let env_map = env(&[("A", "${B:-x}"), ("B", "$A")]);
+        let expansion = Expander::new(&env_map, ReferenceSyntax::Posix).expand_as("A", "${B:-x}");
+        assert_eq!(
+            expansion.issues,
+            vec![ExpansionIssue::Cycle { chain: vec!["A".into(), "B".into(), "A".into()] }]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+// Versioned JSON export document: schema, validation and migration of older versions
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::commands::EnvironmentVariable;
+
+pub const SCHEMA_ID: &str = "env-manager/export";
+pub const CURRENT_VERSION: u64 = 2;
+pub const SOURCE_BACKEND: &str = "windows-registry";
+
+// 版本历史：
+// 1  {"version": "1.0", "exportedAt", "variables": [EnvironmentVariable]}，直接序列化内部结构
+// 2  增加 schema 和 source，变量使用独立于 EnvironmentVariable 的 ExportedVariable
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportDocument {
+    pub schema: String,
+    pub version: u64,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    // 旧版本迁移而来的文件没有来源信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<ExportSource>,
+    pub variables: Vec<ExportedVariable>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportSource {
+    #[serde(rename = "appVersion")]
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub backend: String,
+}
+
+impl ExportSource {
+    pub fn current() -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            backend: SOURCE_BACKEND.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportedVariable {
+    pub name: String,
+    pub value: String,
+    // "user" 或 "system"
+    pub scope: String,
+    // 注册表中的类型是否为 REG_EXPAND_SZ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expandable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+    #[serde(rename = "createdAt", skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(rename = "updatedAt", skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    // 敏感变量脱敏导出，value 为空
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+}
+
+impl ExportedVariable {
+    pub fn from_variable(var: &EnvironmentVariable, expandable: bool, include_metadata: bool) -> Self {
+        Self {
+            name: var.name.clone(),
+            value: var.value.clone(),
+            scope: var.var_type.clone(),
+            expandable,
+            remark: var.remark.clone().filter(|_| include_metadata),
+            created_at: Some(var.created_at).filter(|_| include_metadata),
+            updated_at: Some(var.updated_at).filter(|_| include_metadata),
+            redacted: false,
+        }
+    }
+
+    pub fn into_variable(self) -> EnvironmentVariable {
+        EnvironmentVariable {
+            id: format!("{}_{}", self.scope, self.name),
+            name: self.name,
+            value: self.value,
+            var_type: self.scope,
+            remark: self.remark,
+            created_at: self.created_at.unwrap_or(0),
+            updated_at: self.updated_at.unwrap_or(0),
+            is_valid: true,
+            sensitive: false,
+            expandable: self.expandable,
+        }
+    }
+}
+
+// 文档中的问题，path 形如 variables[3].scope
+#[derive(Debug, Serialize, Clone)]
+pub struct SchemaIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl SchemaIssue {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+// 读取并迁移后的文档；migrated_from 为原始版本（已是当前版本时为空）
+#[derive(Debug, Clone)]
+pub struct LoadedDocument {
+    pub document: ExportDocument,
+    pub migrated_from: Option<u64>,
+}
+
+// 当前版本的 JSON Schema，供外部工具校验导出文件
+pub fn json_schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "env-manager export",
+        "type": "object",
+        "required": ["schema", "version", "exportedAt", "variables"],
+        "properties": {
+            "schema": { "const": SCHEMA_ID },
+            "version": { "const": CURRENT_VERSION },
+            "exportedAt": { "type": "string" },
+            "source": {
+                "type": "object",
+                "required": ["appVersion", "os", "arch", "backend"],
+                "properties": {
+                    "appVersion": { "type": "string" },
+                    "os": { "type": "string" },
+                    "arch": { "type": "string" },
+                    "backend": { "type": "string" }
+                }
+            },
+            "variables": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "value", "scope"],
+                    "properties": {
+                        "name": { "type": "string", "minLength": 1 },
+                        "value": { "type": "string" },
+                        "scope": { "enum": ["user", "system"] },
+                        "expandable": { "type": "boolean" },
+                        "remark": { "type": ["string", "null"] },
+                        "createdAt": { "type": "integer", "minimum": 0 },
+                        "updatedAt": { "type": "integer", "minimum": 0 },
+                        "redacted": { "type": "boolean" }
+                    }
+                }
+            }
+        }
+    })
+}
+
+pub fn new_document(exported_at: String, variables: Vec<ExportedVariable>) -> ExportDocument {
+    ExportDocument {
+        schema: SCHEMA_ID.to_string(),
+        version: CURRENT_VERSION,
+        exported_at,
+        source: Some(ExportSource::current()),
+        variables,
+    }
+}
+
+// 1.0 版本写的是字符串 "1.0"
+fn document_version(root: &Value) -> Result<u64, SchemaIssue> {
+    match root.get("version") {
+        Some(Value::String(version)) if version == "1.0" || version == "1" => Ok(1),
+        Some(Value::Number(number)) => number
+            .as_u64()
+            .filter(|&version| version >= 1)
+            .ok_or_else(|| SchemaIssue::new("version", format!("无效的版本号 {}", number))),
+        Some(other) => Err(SchemaIssue::new("version", format!("无效的版本号 {}", other))),
+        None => Err(SchemaIssue::new("version", "缺少版本号")),
+    }
+}
+
+fn check_string(issues: &mut Vec<SchemaIssue>, item: &Value, path: &str, field: &str, required: bool) {
+    match item.get(field) {
+        Some(Value::String(_)) => {}
+        None | Some(Value::Null) if !required => {}
+        None => issues.push(SchemaIssue::new(format!("{}.{}", path, field), "缺少字段")),
+        Some(_) => issues.push(SchemaIssue::new(format!("{}.{}", path, field), "应为字符串")),
+    }
+}
+
+fn check_timestamp(issues: &mut Vec<SchemaIssue>, item: &Value, path: &str, field: &str, required: bool) {
+    match item.get(field) {
+        Some(value) if value.is_u64() => {}
+        None if !required => {}
+        None => issues.push(SchemaIssue::new(format!("{}.{}", path, field), "缺少字段")),
+        Some(_) => issues.push(SchemaIssue::new(format!("{}.{}", path, field), "应为非负整数")),
+    }
+}
+
+fn check_variables(
+    issues: &mut Vec<SchemaIssue>,
+    root: &Value,
+    check_item: impl Fn(&mut Vec<SchemaIssue>, &Value, &str),
+) {
+    let Some(variables) = root.get("variables") else {
+        issues.push(SchemaIssue::new("variables", "缺少字段"));
+        return;
+    };
+    let Some(variables) = variables.as_array() else {
+        issues.push(SchemaIssue::new("variables", "应为数组"));
+        return;
+    };
+    for (i, item) in variables.iter().enumerate() {
+        let path = format!("variables[{}]", i);
+        if !item.is_object() {
+            issues.push(SchemaIssue::new(path, "应为对象"));
+            continue;
+        }
+        check_item(issues, item, &path);
+        if item.get("name").and_then(Value::as_str) == Some("") {
+            issues.push(SchemaIssue::new(format!("{}.name", path), "不能为空"));
+        }
+    }
+}
+
+fn check_scope(issues: &mut Vec<SchemaIssue>, item: &Value, path: &str, field: &str) {
+    match item.get(field).and_then(Value::as_str) {
+        Some("user") | Some("system") => {}
+        Some(other) => issues.push(SchemaIssue::new(
+            format!("{}.{}", path, field),
+            format!("'{}' 不是有效的作用域，应为 user 或 system", other),
+        )),
+        None => issues.push(SchemaIssue::new(format!("{}.{}", path, field), "缺少字段或不是字符串")),
+    }
+}
+
+fn validate_v1(root: &Value) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+    check_variables(&mut issues, root, |issues, item, path| {
+        check_string(issues, item, path, "name", true);
+        check_string(issues, item, path, "value", true);
+        check_scope(issues, item, path, "type");
+        check_string(issues, item, path, "remark", false);
+        check_timestamp(issues, item, path, "createdAt", false);
+        check_timestamp(issues, item, path, "updatedAt", false);
+    });
+    issues
+}
+
+fn validate_v2(root: &Value) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+    if root.get("schema").and_then(Value::as_str) != Some(SCHEMA_ID) {
+        issues.push(SchemaIssue::new("schema", format!("应为 \"{}\"", SCHEMA_ID)));
+    }
+    check_string(&mut issues, root, "$", "exportedAt", true);
+    if let Some(source) = root.get("source") {
+        if source.is_object() {
+            for field in ["appVersion", "os", "arch", "backend"] {
+                check_string(&mut issues, source, "source", field, true);
+            }
+        } else {
+            issues.push(SchemaIssue::new("source", "应为对象"));
+        }
+    }
+    check_variables(&mut issues, root, |issues, item, path| {
+        check_string(issues, item, path, "name", true);
+        check_string(issues, item, path, "value", true);
+        check_scope(issues, item, path, "scope");
+        for field in ["expandable", "redacted"] {
+            if item.get(field).is_some_and(|value| !value.is_boolean()) {
+                issues.push(SchemaIssue::new(format!("{}.{}", path, field), "应为布尔值"));
+            }
+        }
+        check_string(issues, item, path, "remark", false);
+        check_timestamp(issues, item, path, "createdAt", false);
+        check_timestamp(issues, item, path, "updatedAt", false);
+    });
+    issues
+}
+
+// 1 -> 2：type 改名为 scope，去掉 id 和 isValid，补上 schema
+fn migrate_v1(root: Value) -> Value {
+    let variables: Vec<Value> = root["variables"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| {
+            let mut migrated = json!({
+                "name": item["name"],
+                "value": item["value"],
+                "scope": item["type"],
+            });
+            for field in ["remark", "createdAt", "updatedAt"] {
+                if let Some(value) = item.get(field).filter(|value| !value.is_null()) {
+                    migrated[field] = value.clone();
+                }
+            }
+            migrated
+        })
+        .collect();
+    json!({
+        "schema": SCHEMA_ID,
+        "version": 2,
+        "exportedAt": root.get("exportedAt").cloned().unwrap_or_else(|| json!("")),
+        "variables": variables,
+    })
+}
+
+// 每个版本的校验函数，以及迁移到下一版本的函数（当前版本没有）
+type Validator = fn(&Value) -> Vec<SchemaIssue>;
+type Migration = fn(Value) -> Value;
+
+fn version_steps(version: u64) -> Option<(Validator, Option<Migration>)> {
+    match version {
+        1 => Some((validate_v1, Some(migrate_v1))),
+        2 => Some((validate_v2, None)),
+        _ => None,
+    }
+}
+
+// 校验文档并逐版本迁移到当前版本
+pub fn load(root: Value) -> Result<LoadedDocument, Vec<SchemaIssue>> {
+    if !root.is_object() {
+        return Err(vec![SchemaIssue::new("$", "导出文件的根节点应为对象")]);
+    }
+    let original = document_version(&root).map_err(|issue| vec![issue])?;
+    if original > CURRENT_VERSION {
+        return Err(vec![SchemaIssue::new(
+            "version",
+            format!("文件由更新版本的程序导出（格式版本 {}），当前支持到版本 {}，请升级后再导入", original, CURRENT_VERSION),
+        )]);
+    }
+
+    let mut version = original;
+    let mut root = root;
+    loop {
+        let Some((validate, migrate)) = version_steps(version) else {
+            return Err(vec![SchemaIssue::new("version", format!("不支持的格式版本 {}", version))]);
+        };
+        let issues = validate(&root);
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+        match migrate {
+            Some(migrate) => {
+                root = migrate(root);
+                version += 1;
+            }
+            None => break,
+        }
+    }
+
+    let document: ExportDocument = serde_json::from_value(root)
+        .map_err(|e| vec![SchemaIssue::new("$", format!("Failed to parse export document: {}", e))])?;
+    Ok(LoadedDocument {
+        document,
+        migrated_from: Some(original).filter(|&version| version != CURRENT_VERSION),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    #[test]
+    fn migrates_version_1_documents() {
+        let root = json!({
+            "version": "1.0",
+            "exportedAt": "2024-01-01",
+            "variables": [
+                { "id": "user_A", "name": "A", "value": "1", "type": "user", "remark": null, "createdAt": 5, "updatedAt": 6, "isValid": true },
+                { "id": "system_B", "name": "B", "value": "%A%", "type": "system", "remark": "note" }
+            ]
+        });
+        let loaded = load(root).unwrap();
+        assert_eq!(loaded.migrated_from, Some(1));
+        assert_eq!(loaded.document.version, CURRENT_VERSION);
+        assert_eq!(loaded.document.schema, SCHEMA_ID);
+        assert!(loaded.document.source.is_none());
+
+        let variables: Vec<EnvironmentVariable> = loaded.document.variables.into_iter().map(ExportedVariable::into_variable).collect();
+        assert_eq!(variables[0].id, "user_A");
+        assert_eq!((variables[0].created_at, variables[0].updated_at), (5, 6));
+        assert_eq!(variables[0].remark, None);
+        assert_eq!(variables[1].id, "system_B");
+        assert_eq!(variables[1].remark.as_deref(), Some("note"));
+    }
+
+    #[test]
+    fn round_trips_current_documents_with_expandable_values() {
+        let mut variable = var("user", "Path", "%USERPROFILE%\\bin");
+        variable.remark = Some("r".to_string());
+        variable.expandable = true;
+        let document = new_document("now".to_string(), vec![ExportedVariable::from_variable(&variable, true, false)]);
+        let loaded = load(serde_json::to_value(&document).unwrap()).unwrap();
+        assert_eq!(loaded.migrated_from, None);
+
+        let imported = loaded.document.variables.into_iter().next().unwrap().into_variable();
+        assert_eq!(imported.id, variable.id);
+        assert_eq!(imported.value, variable.value);
+        assert!(imported.expandable);
+        assert_eq!(imported.remark, None);
+    }
+
+    #[test]
+    fn reports_issue_paths() {
+        let issues = load(json!({
+            "schema": SCHEMA_ID,
+            "version": 2,
+            "exportedAt": "now",
+            "variables": [{ "name": "", "value": 1, "scope": "machine", "expandable": "yes" }]
+        }))
+        .unwrap_err();
+        let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["variables[0].value", "variables[0].scope", "variables[0].expandable", "variables[0].name"]
+        );
+
+        let issues = load(json!({ "version": 1, "variables": [{ "name": "A", "value": "1", "type": "both" }] })).unwrap_err();
+        assert_eq!(issues[0].path, "variables[0].type");
+    }
+
+    #[test]
+    fn rejects_unknown_and_newer_versions() {
+        assert_eq!(load(json!({ "variables": [] })).unwrap_err()[0].path, "version");
+        assert_eq!(load(json!({ "version": CURRENT_VERSION + 1, "variables": [] })).unwrap_err()[0].path, "version");
+        assert_eq!(load(json!([])).unwrap_err()[0].path, "$");
+    }
+}
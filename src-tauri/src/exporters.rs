@@ -0,0 +1,662 @@
+// Exporters that render environment variables as dotenv, shell scripts, .reg files and CSV, redacting secrets
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::commands::{EnvironmentVariable, SearchInput};
+use crate::export_schema::{self, ExportedVariable};
+use crate::limits;
+
+const USER_KEY: &str = "HKEY_CURRENT_USER\\Environment";
+const SYSTEM_KEY: &str = "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Dotenv,
+    // bash 与 zsh 共用 export 语法
+    #[serde(alias = "zsh")]
+    Bash,
+    Fish,
+    // [Environment]::SetEnvironmentVariable，写入用户或系统作用域
+    Powershell,
+    // $env:，只影响当前会话
+    PowershellSession,
+    Cmd,
+    Reg,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Dotenv => "env",
+            ExportFormat::Bash => "sh",
+            ExportFormat::Fish => "fish",
+            ExportFormat::Powershell | ExportFormat::PowershellSession => "ps1",
+            ExportFormat::Cmd => "cmd",
+            ExportFormat::Reg => "reg",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    fn line_ending(&self) -> &'static str {
+        match self {
+            ExportFormat::Powershell
+            | ExportFormat::PowershellSession
+            | ExportFormat::Cmd
+            | ExportFormat::Reg
+            | ExportFormat::Csv => "\r\n",
+            _ => "\n",
+        }
+    }
+}
+
+// 导出选项；ids、query、scope 同时给出时取交集
+#[derive(Debug, Deserialize, Default)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub ids: Option<Vec<String>>,
+    pub query: Option<SearchInput>,
+    // "user" 或 "system"
+    pub scope: Option<String>,
+    // 指定输出文件；为空时写入桌面上带时间戳的文件
+    #[serde(rename = "outputPath")]
+    pub output_path: Option<String>,
+    // 只返回内容，不写文件
+    #[serde(rename = "inMemory", default)]
+    pub in_memory: bool,
+    // 是否包含备注、创建和修改时间等元数据
+    #[serde(rename = "includeMetadata", default = "default_true")]
+    pub include_metadata: bool,
+    // 设置后输出加密的导出文件，只支持 JSON 格式
+    pub password: Option<String>,
+    // 是否导出敏感变量的值，默认脱敏
+    #[serde(rename = "includeSecrets", default)]
+    pub include_secrets: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportOutput {
+    pub format: ExportFormat,
+    pub count: usize,
+    // 写入的文件路径，inMemory 时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // inMemory 时返回的内容
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    // 被跳过、脱敏或可能被截断的变量
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+// 导出所需的数据；expandable 为注册表中类型是 REG_EXPAND_SZ 的变量 ID，redacted 为需要脱敏的变量 ID
+pub struct ExportInput<'a> {
+    pub variables: &'a [EnvironmentVariable],
+    pub expandable: &'a HashSet<String>,
+    pub redacted: &'a HashSet<String>,
+    pub exported_at: String,
+    pub include_metadata: bool,
+}
+
+impl ExportInput<'_> {
+    // 写成注释的备注，不包含元数据或没有备注时为空
+    fn remark(&self, var: &EnvironmentVariable) -> Option<String> {
+        let remark = var.remark.as_deref().filter(|_| self.include_metadata)?.trim();
+        if remark.is_empty() {
+            return None;
+        }
+        Some(remark.replace(['\r', '\n'], " "))
+    }
+
+    fn is_redacted(&self, var: &EnvironmentVariable) -> bool {
+        self.redacted.contains(&var.id)
+    }
+
+    // 逐个变量生成语句，备注写在语句上方；脱敏的变量只留下一行注释
+    fn statements(&self, comment: &str, statement: impl Fn(&EnvironmentVariable) -> String) -> Vec<String> {
+        let mut lines = Vec::new();
+        for var in self.variables {
+            if let Some(remark) = self.remark(var) {
+                lines.push(format!("{} {}", comment, remark));
+            }
+            if self.is_redacted(var) {
+                lines.push(redacted(comment, var));
+            } else {
+                lines.push(statement(var));
+            }
+        }
+        lines
+    }
+}
+
+// 按格式生成导出内容
+pub fn render(format: ExportFormat, input: &ExportInput) -> Result<String, String> {
+    let lines = match format {
+        ExportFormat::Json => return render_json(input),
+        ExportFormat::Dotenv => render_dotenv(input),
+        ExportFormat::Bash => render_bash(input),
+        ExportFormat::Fish => render_fish(input),
+        ExportFormat::Powershell => render_powershell(input),
+        ExportFormat::PowershellSession => render_powershell_session(input),
+        ExportFormat::Cmd => render_cmd(input),
+        ExportFormat::Reg => render_reg(input),
+        ExportFormat::Csv => render_csv(input),
+    };
+    let newline = format.line_ending();
+    Ok(lines.join(newline) + newline)
+}
+
+// 导出内容中被跳过、脱敏或可能被截断的变量，与生成的注释一致
+pub fn warnings(format: ExportFormat, input: &ExportInput) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for var in input.variables {
+        if input.is_redacted(var) {
+            warnings.push(format!("已脱敏 {}: 敏感变量的值未导出", var.name));
+            continue;
+        }
+        match format {
+            ExportFormat::Dotenv | ExportFormat::Bash | ExportFormat::Fish if !is_portable_name(&var.name) => {
+                warnings.push(format!("已跳过 {}: 变量名不是合法的标识符", var.name));
+            }
+            ExportFormat::Cmd => {
+                if let Some(reason) = cmd_skip_reason(var) {
+                    warnings.push(format!("已跳过 {}: {}", var.name, reason));
+                } else if var.value.chars().count() > limits::SETX_LIMIT {
+                    warnings.push(format!("{} 超过 {} 个字符，setx 会截断该值", var.name, limits::SETX_LIMIT));
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+// 写入文件的字节；regedit 要求 5.00 版本的 .reg 文件为带 BOM 的 UTF-16LE
+pub fn encode(format: ExportFormat, content: &str) -> Vec<u8> {
+    match format {
+        ExportFormat::Reg => {
+            let mut bytes = vec![0xFF, 0xFE];
+            for unit in content.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+            bytes
+        }
+        // PowerShell 5 将无 BOM 的脚本按系统代码页读取
+        ExportFormat::Powershell | ExportFormat::PowershellSession => {
+            let mut bytes = vec![0xEF, 0xBB, 0xBF];
+            bytes.extend_from_slice(content.as_bytes());
+            bytes
+        }
+        _ => content.as_bytes().to_vec(),
+    }
+}
+
+fn render_json(input: &ExportInput) -> Result<String, String> {
+    let variables = input
+        .variables
+        .iter()
+        .map(|var| {
+            let mut exported =
+                ExportedVariable::from_variable(var, input.expandable.contains(&var.id), input.include_metadata);
+            if input.is_redacted(var) {
+                exported.value = String::new();
+                exported.redacted = true;
+            }
+            exported
+        })
+        .collect();
+    let document = export_schema::new_document(input.exported_at.clone(), variables);
+    serde_json::to_string_pretty(&document).map_err(|e| format!("Failed to serialize data: {}", e))
+}
+
+// POSIX shell、fish 和 dotenv 只接受这种形式的变量名，例如 ProgramFiles(x86) 无法导出
+fn is_portable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn skipped(comment: &str, var: &EnvironmentVariable) -> String {
+    format!("{} 已跳过 {}: 变量名不是合法的标识符", comment, var.name)
+}
+
+fn redacted(comment: &str, var: &EnvironmentVariable) -> String {
+    format!("{} 已脱敏 {}: 敏感变量的值未导出", comment, var.name)
+}
+
+fn dotenv_value(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:,@+".contains(c));
+    if is_plain {
+        return value.to_string();
+    }
+    // 单引号内的内容不做转义和插值
+    if !value.contains('\'') && !value.contains('\n') && !value.contains('\r') {
+        return format!("'{}'", value);
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn render_dotenv(input: &ExportInput) -> Vec<String> {
+    input.statements("#", |var| {
+        if is_portable_name(&var.name) {
+            format!("{}={}", var.name, dotenv_value(&var.value))
+        } else {
+            skipped("#", var)
+        }
+    })
+}
+
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn render_bash(input: &ExportInput) -> Vec<String> {
+    let mut lines = vec!["#!/usr/bin/env bash".to_string()];
+    lines.extend(input.statements("#", |var| {
+        if is_portable_name(&var.name) {
+            format!("export {}={}", var.name, posix_quote(&var.value))
+        } else {
+            skipped("#", var)
+        }
+    }));
+    lines
+}
+
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn render_fish(input: &ExportInput) -> Vec<String> {
+    input.statements("#", |var| {
+        if is_portable_name(&var.name) {
+            format!("set -gx {} {}", var.name, fish_quote(&var.value))
+        } else {
+            skipped("#", var)
+        }
+    })
+}
+
+// PowerShell 单引号字符串中只需把 ' 写成 ''
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn render_powershell(input: &ExportInput) -> Vec<String> {
+    input.statements("#", |var| {
+        let target = if var.var_type == "system" { "Machine" } else { "User" };
+        format!(
+            "[Environment]::SetEnvironmentVariable({}, {}, '{}')",
+            powershell_quote(&var.name),
+            powershell_quote(&var.value),
+            target
+        )
+    })
+}
+
+fn render_powershell_session(input: &ExportInput) -> Vec<String> {
+    input.statements("#", |var| {
+        let target = if is_portable_name(&var.name) {
+            format!("$env:{}", var.name)
+        } else {
+            format!("${{env:{}}}", var.name.replace('}', "`}"))
+        };
+        format!("{} = {}", target, powershell_quote(&var.value))
+    })
+}
+
+// setx 无法原样写入的变量返回跳过的原因
+fn cmd_skip_reason(var: &EnvironmentVariable) -> Option<&'static str> {
+    if var.value.contains('\n') || var.value.contains('\r') {
+        Some("setx 不支持多行值")
+    } else if var.name.contains('"') || var.value.contains('"') {
+        // cmd 遇到引号会切换特殊字符的解析，\" 挡不住其后的 & 和 |，无法安全转义
+        Some("包含双引号，无法在批处理中安全转义")
+    } else {
+        None
+    }
+}
+
+// 批处理中 % 需要写成 %% 才能保留引用原样；setx 按命令行规则解析参数，
+// 结尾的 \ 会把后面的引号转义成字面值，需要加倍
+fn cmd_quote(value: &str) -> String {
+    let escaped = value.replace('%', "%%");
+    let trailing = escaped.len() - escaped.trim_end_matches('\\').len();
+    format!("\"{}{}\"", escaped, "\\".repeat(trailing))
+}
+
+fn render_cmd(input: &ExportInput) -> Vec<String> {
+    let mut lines = vec!["@echo off".to_string()];
+    for var in input.variables {
+        if let Some(remark) = input.remark(var) {
+            lines.push(format!("REM {}", remark));
+        }
+        if input.is_redacted(var) {
+            lines.push(redacted("REM", var));
+            continue;
+        }
+        if let Some(reason) = cmd_skip_reason(var) {
+            lines.push(format!("REM 已跳过 {}: {}", var.name, reason));
+            continue;
+        }
+        if var.value.chars().count() > limits::SETX_LIMIT {
+            lines.push(format!(
+                "REM 警告: {} 超过 {} 个字符，setx 会截断该值",
+                var.name,
+                limits::SETX_LIMIT
+            ));
+        }
+        let machine = if var.var_type == "system" { " /M" } else { "" };
+        lines.push(format!("setx {} {}{}", cmd_quote(&var.name), cmd_quote(&var.value), machine));
+    }
+    lines
+}
+
+fn reg_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// REG_EXPAND_SZ 以 hex(2) 写出：UTF-16LE 字节加结尾的 0，按 regedit 的格式每行约 80 个字符折行
+fn reg_expand_string(prefix_len: usize, value: &str) -> String {
+    let mut bytes: Vec<u8> = value.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    bytes.extend_from_slice(&[0, 0]);
+
+    let mut out = String::from("hex(2):");
+    let mut column = prefix_len + out.len();
+    for (i, byte) in bytes.iter().enumerate() {
+        let last = i + 1 == bytes.len();
+        let piece = if last { format!("{:02x}", byte) } else { format!("{:02x},", byte) };
+        if column + piece.len() > 78 && !last {
+            out.push_str("\\\r\n  ");
+            column = 2;
+        }
+        out.push_str(&piece);
+        column += piece.len();
+    }
+    out
+}
+
+fn render_reg(input: &ExportInput) -> Vec<String> {
+    let mut lines = vec!["Windows Registry Editor Version 5.00".to_string()];
+    for (scope, key) in [("user", USER_KEY), ("system", SYSTEM_KEY)] {
+        let scoped: Vec<&EnvironmentVariable> =
+            input.variables.iter().filter(|var| var.var_type == scope).collect();
+        if scoped.is_empty() {
+            continue;
+        }
+        lines.push(String::new());
+        lines.push(format!("[{}]", key));
+        for var in scoped {
+            if let Some(remark) = input.remark(var) {
+                lines.push(format!("; {}", remark));
+            }
+            if input.is_redacted(var) {
+                lines.push(redacted(";", var));
+                continue;
+            }
+            let name = reg_string(&var.name);
+            let data = if input.expandable.contains(&var.id) {
+                reg_expand_string(name.len() + 1, &var.value)
+            } else {
+                reg_string(&var.value)
+            };
+            lines.push(format!("{}={}", name, data));
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(input: &ExportInput) -> Vec<String> {
+    let header = if input.include_metadata {
+        "name,value,type,remark,createdAt,updatedAt"
+    } else {
+        "name,value,type"
+    };
+    let mut lines = vec![header.to_string()];
+    // CSV 没有注释行，脱敏的变量直接省略
+    lines.extend(input.variables.iter().filter(|var| !input.is_redacted(var)).map(|var| {
+        let mut fields = vec![csv_field(&var.name), csv_field(&var.value), csv_field(&var.var_type)];
+        if input.include_metadata {
+            fields.push(csv_field(var.remark.as_deref().unwrap_or_default()));
+            fields.push(var.created_at.to_string());
+            fields.push(var.updated_at.to_string());
+        }
+        fields.join(",")
+    }));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn render_with(
+        format: ExportFormat,
+        variables: &[EnvironmentVariable],
+        expandable: &[&str],
+        redacted: &[&str],
+        include_metadata: bool,
+    ) -> String {
+        let input = ExportInput {
+            variables,
+            expandable: &ids(expandable),
+            redacted: &ids(redacted),
+            exported_at: "now".to_string(),
+            include_metadata,
+        };
+        render(format, &input).unwrap()
+    }
+
+    fn export(format: ExportFormat, variables: &[EnvironmentVariable], expandable: &[&str]) -> String {
+        render_with(format, variables, expandable, &[], true)
+    }
+
+    #[test]
+    fn quotes_dotenv_values_and_skips_non_portable_names() {
+        let variables = vec![
+            var("user", "PLAIN", "a-b/c"),
+            var("user", "SPACED", "C:\\Program Files"),
+            var("user", "MIXED", "it's $HOME\n\"x\""),
+            var("system", "ProgramFiles(x86)", "C:\\x"),
+        ];
+        let output = export(ExportFormat::Dotenv, &variables, &[]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "PLAIN=a-b/c",
+                "SPACED='C:\\Program Files'",
+                r#"MIXED="it's \$HOME\n\"x\"""#,
+                "# 已跳过 ProgramFiles(x86): 变量名不是合法的标识符",
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_shell_and_powershell_values() {
+        let variables = vec![var("user", "MSG", r"it's C:\x"), var("system", "ProgramFiles(x86)", "C:\\x")];
+
+        let bash = export(ExportFormat::Bash, &variables, &[]);
+        assert!(bash.starts_with("#!/usr/bin/env bash\n"));
+        assert!(bash.contains(r"export MSG='it'\''s C:\x'"));
+
+        let fish = export(ExportFormat::Fish, &variables, &[]);
+        assert!(fish.contains(r"set -gx MSG 'it\'s C:\\x'"));
+
+        let powershell = export(ExportFormat::Powershell, &variables, &[]);
+        assert_eq!(
+            powershell,
+            "[Environment]::SetEnvironmentVariable('MSG', 'it''s C:\\x', 'User')\r\n\
+             [Environment]::SetEnvironmentVariable('ProgramFiles(x86)', 'C:\\x', 'Machine')\r\n"
+        );
+
+        let session = export(ExportFormat::PowershellSession, &variables, &[]);
+        assert!(session.contains("$env:MSG = 'it''s C:\\x'"));
+        assert!(session.contains("${env:ProgramFiles(x86)} = 'C:\\x'"));
+    }
+
+    #[test]
+    fn writes_setx_commands_for_cmd() {
+        let variables = vec![
+            var("user", "JAVA_BIN", r"%JAVA_HOME%\bin"),
+            var("system", "MULTI", "a\nb"),
+            var("system", "LONG", &"x".repeat(limits::SETX_LIMIT + 1)),
+        ];
+        let output = export(ExportFormat::Cmd, &variables, &[]);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "@echo off");
+        assert_eq!(lines[1], r#"setx "JAVA_BIN" "%%JAVA_HOME%%\bin""#);
+        assert_eq!(lines[2], "REM 已跳过 MULTI: setx 不支持多行值");
+        assert!(lines[3].starts_with("REM 警告: LONG"));
+        assert!(lines[4].starts_with("setx \"LONG\"") && lines[4].ends_with(" /M"));
+    }
+
+    #[test]
+    fn writes_reg_files_with_expandable_values_as_hex() {
+        let variables = vec![
+            var("user", "HOME_DIR", "C:\\Users\\\"me\""),
+            var("user", "JAVA_BIN", "%JAVA_HOME%\\bin"),
+            var("system", "SHORT", "%A%"),
+        ];
+        let output = export(ExportFormat::Reg, &variables, &["user_JAVA_BIN", "system_SHORT"]);
+        let lines: Vec<&str> = output.split("\r\n").collect();
+
+        assert_eq!(lines[0], "Windows Registry Editor Version 5.00");
+        assert_eq!(lines[2], format!("[{}]", USER_KEY));
+        assert_eq!(lines[3], r#""HOME_DIR"="C:\\Users\\\"me\"""#);
+        // 超过 80 列时以 \ 续行，续行以两个空格缩进
+        assert!(lines[4].starts_with("\"JAVA_BIN\"=hex(2):25,00,4a,00") && lines[4].ends_with("\\"));
+        assert!(lines[4].len() <= 80);
+        assert!(lines[5].starts_with("  ") && lines[5].ends_with("00,00"));
+        assert_eq!(lines[8], r#""SHORT"=hex(2):25,00,41,00,25,00,00,00"#);
+
+        let bytes = encode(ExportFormat::Reg, "R");
+        assert_eq!(bytes, vec![0xFF, 0xFE, b'R', 0]);
+        assert_eq!(encode(ExportFormat::Powershell, "R"), vec![0xEF, 0xBB, 0xBF, b'R']);
+        assert_eq!(encode(ExportFormat::Bash, "R"), vec![b'R']);
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let mut variable = var("user", "LIST", "a,b");
+        variable.remark = Some("say \"hi\"".to_string());
+        variable.created_at = 1;
+        variable.updated_at = 2;
+        let output = export(ExportFormat::Csv, &[variable], &[]);
+
+        assert_eq!(output, "name,value,type,remark,createdAt,updatedAt\r\nLIST,\"a,b\",user,\"say \"\"hi\"\"\",1,2\r\n");
+    }
+
+    #[test]
+    fn writes_remarks_as_comments_only_with_metadata() {
+        let mut variable = var("user", "JAVA_HOME", "C:\\jdk");
+        variable.remark = Some("JDK 17\nLTS".to_string());
+        variable.created_at = 5;
+        let variables = vec![variable];
+
+        let bash = render_with(ExportFormat::Bash, &variables, &[], &[], true);
+        assert!(bash.contains("# JDK 17 LTS\nexport JAVA_HOME="));
+        let reg = render_with(ExportFormat::Reg, &variables, &[], &[], true);
+        assert!(reg.contains("; JDK 17 LTS\r\n\"JAVA_HOME\""));
+
+        let bash = render_with(ExportFormat::Bash, &variables, &[], &[], false);
+        assert!(!bash.contains("JDK 17"));
+        let csv = render_with(ExportFormat::Csv, &variables, &[], &[], false);
+        assert_eq!(csv, "name,value,type\r\nJAVA_HOME,C:\\jdk,user\r\n");
+
+        let json: serde_json::Value = serde_json::from_str(&render_with(ExportFormat::Json, &variables, &[], &[], false)).unwrap();
+        let exported = json["variables"][0].as_object().unwrap();
+        assert!(!exported.contains_key("remark") && !exported.contains_key("createdAt"));
+        assert_eq!(exported["value"], "C:\\jdk");
+    }
+
+    #[test]
+    fn redacts_sensitive_values_in_every_format() {
+        let variables = vec![var("user", "API_TOKEN", "s3cret"), var("user", "PLAIN", "ok")];
+        let redacted = ["user_API_TOKEN"];
+
+        for format in [ExportFormat::Dotenv, ExportFormat::Bash, ExportFormat::Powershell, ExportFormat::Cmd, ExportFormat::Reg] {
+            let output = render_with(format, &variables, &[], &redacted, true);
+            assert!(!output.contains("s3cret"), "{:?}", format);
+            assert!(output.contains("已脱敏 API_TOKEN"), "{:?}", format);
+            assert!(output.contains("ok"), "{:?}", format);
+        }
+
+        let csv = render_with(ExportFormat::Csv, &variables, &[], &redacted, false);
+        assert_eq!(csv, "name,value,type\r\nPLAIN,ok,user\r\n");
+
+        let json: serde_json::Value = serde_json::from_str(&render_with(ExportFormat::Json, &variables, &[], &redacted, true)).unwrap();
+        assert_eq!(json["variables"][0]["value"], "");
+        assert_eq!(json["variables"][0]["redacted"], true);
+        assert_eq!(json["variables"][1]["value"], "ok");
+    }
+
+    #[test]
+    fn quotes_setx_arguments() {
+        assert_eq!(cmd_quote(r"%JAVA_HOME%\bin"), r#""%%JAVA_HOME%%\bin""#);
+        assert_eq!(cmd_quote(r"C:\tools\"), r#""C:\tools\\""#);
+        assert_eq!(cmd_quote(r"C:\a\\"), r#""C:\a\\\\""#);
+    }
+
+    #[test]
+    fn skips_cmd_values_that_cannot_be_escaped() {
+        let variables = vec![
+            var("user", "OK", r"C:\tools\"),
+            var("user", "QUOTED", r#"say "hi" & del *"#),
+            var("system", "MULTI", "a\nb"),
+        ];
+        let empty = HashSet::new();
+        let input = ExportInput {
+            variables: &variables,
+            expandable: &empty,
+            redacted: &empty,
+            exported_at: "now".to_string(),
+            include_metadata: false,
+        };
+
+        let output = render(ExportFormat::Cmd, &input).unwrap();
+        let setx: Vec<&str> = output.lines().filter(|line| line.starts_with("setx")).collect();
+        assert_eq!(setx, vec![r#"setx "OK" "C:\tools\\""#]);
+        assert!(!output.contains("del *"));
+
+        let warnings = warnings(ExportFormat::Cmd, &input);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("QUOTED") && warnings[1].contains("MULTI"));
+    }
+}
@@ -0,0 +1,368 @@
+// Import preview and conflict resolution between incoming and existing variables
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::commands::{is_protected_variable, EnvironmentVariable};
+use crate::expand::{Expander, ReferenceSyntax};
+use crate::importers::{Diagnostic, ImportFormat};
+use crate::search::is_path_like;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportStatus {
+    New,
+    // 值与现有变量相同
+    Identical,
+    Conflicting,
+    // 与受保护的系统变量冲突，不会被覆盖
+    Protected,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportStrategy {
+    // 保留现有的值
+    #[default]
+    Skip,
+    Overwrite,
+    // PATH 类变量合并条目，现有条目在前
+    Merge,
+    // 以带后缀的新名称导入，如 JAVA_HOME_IMPORTED
+    KeepBoth,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub strategy: ImportStrategy,
+    // 按导入变量 ID 单独指定的策略，优先于 strategy；对新变量指定 skip 表示不导入
+    #[serde(default)]
+    pub decisions: HashMap<String, ImportStrategy>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PreviewItem {
+    pub variable: EnvironmentVariable,
+    pub status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing: Option<EnvironmentVariable>,
+    // PATH 类变量按 merge 策略合并后的值，没有新增条目时为空
+    #[serde(rename = "mergedValue", skip_serializing_if = "Option::is_none")]
+    pub merged_value: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    pub diagnostics: Vec<Diagnostic>,
+    pub items: Vec<PreviewItem>,
+}
+
+pub enum Action {
+    Add(EnvironmentVariable),
+    Update { id: String, variable: EnvironmentVariable },
+    Skip(String),
+    // 按所选策略无法处理，计为失败
+    Reject(String),
+}
+
+pub struct PlannedImport {
+    pub item: PreviewItem,
+    pub strategy: &'static str,
+    pub action: Action,
+}
+
+// 单个导入变量的结果；target 为实际写入的变量名
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportResult {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub var_type: String,
+    pub status: ImportStatus,
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ImportResult {
+    pub fn new(planned: &PlannedImport, outcome: Result<Option<String>, String>) -> Self {
+        let skipped = match &planned.action {
+            Action::Skip(reason) => Some(reason.clone()),
+            _ => None,
+        };
+        let action = if skipped.is_some() { "skip" } else { planned.strategy };
+        let (success, target, message) = match outcome {
+            Ok(target) => (true, target, skipped),
+            Err(error) => (false, None, Some(error)),
+        };
+        Self {
+            name: planned.item.variable.name.clone(),
+            var_type: planned.item.variable.var_type.clone(),
+            status: planned.item.status,
+            action: action.to_string(),
+            target,
+            success,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportReport {
+    pub format: ImportFormat,
+    pub diagnostics: Vec<Diagnostic>,
+    pub results: Vec<ImportResult>,
+}
+
+// 比较路径条目时忽略大小写和结尾的反斜杠，并按展开后的值去重
+fn entry_key(expander: &mut Expander, entry: &str) -> String {
+    let expanded = expander.expand(entry.trim()).expanded;
+    expanded.trim_end_matches(['\\', '/']).to_lowercase()
+}
+
+// 把导入值中现有值没有的条目追加到末尾，没有新增条目时返回 None
+fn merge_entries(expander: &mut Expander, existing: &str, incoming: &str) -> Option<String> {
+    let mut seen: HashSet<String> = existing
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| entry_key(expander, entry))
+        .collect();
+    let added: Vec<&str> = incoming
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter(|entry| seen.insert(entry_key(expander, entry)))
+        .collect();
+    if added.is_empty() {
+        return None;
+    }
+
+    let mut merged = existing.trim_end_matches(';').to_string();
+    for entry in added {
+        if !merged.is_empty() {
+            merged.push(';');
+        }
+        merged.push_str(entry);
+    }
+    Some(merged)
+}
+
+// 将导入的变量与现有变量逐个比较
+pub fn preview(
+    incoming: &[EnvironmentVariable],
+    current: &[EnvironmentVariable],
+    env_map: &HashMap<String, String>,
+    syntax: ReferenceSyntax,
+) -> Vec<PreviewItem> {
+    let mut expander = Expander::new(env_map, syntax);
+    incoming
+        .iter()
+        .map(|var| {
+            let existing = current
+                .iter()
+                .find(|existing| existing.var_type == var.var_type && existing.name.eq_ignore_ascii_case(&var.name));
+            let Some(existing) = existing else {
+                return PreviewItem {
+                    variable: var.clone(),
+                    status: ImportStatus::New,
+                    existing: None,
+                    merged_value: None,
+                };
+            };
+
+            let status = if existing.value == var.value {
+                ImportStatus::Identical
+            } else if is_protected_variable(&existing.var_type, &existing.name) {
+                ImportStatus::Protected
+            } else {
+                ImportStatus::Conflicting
+            };
+            let merged_value = if status != ImportStatus::Identical
+                && (is_path_like(&existing.name, &existing.value) || is_path_like(&var.name, &var.value))
+            {
+                merge_entries(&mut expander, &existing.value, &var.value)
+            } else {
+                None
+            };
+            PreviewItem {
+                variable: var.clone(),
+                status,
+                existing: Some(existing.clone()),
+                merged_value,
+            }
+        })
+        .collect()
+}
+
+// 同一作用域内未被占用的带后缀名称
+fn suffixed_name(name: &str, scope: &str, taken: &mut HashSet<String>) -> String {
+    let mut candidate = format!("{}_IMPORTED", name);
+    let mut n = 2;
+    while taken.contains(&format!("{}_{}", scope, candidate).to_uppercase()) {
+        candidate = format!("{}_IMPORTED_{}", name, n);
+        n += 1;
+    }
+    taken.insert(format!("{}_{}", scope, candidate).to_uppercase());
+    candidate
+}
+
+fn strategy_name(strategy: ImportStrategy) -> &'static str {
+    match strategy {
+        ImportStrategy::Skip => "skip",
+        ImportStrategy::Overwrite => "overwrite",
+        ImportStrategy::Merge => "merge",
+        ImportStrategy::KeepBoth => "keep-both",
+    }
+}
+
+// 按策略决定每个导入变量的写入方式
+pub fn plan(items: Vec<PreviewItem>, current: &[EnvironmentVariable], options: &ImportOptions) -> Vec<PlannedImport> {
+    let mut taken: HashSet<String> = current
+        .iter()
+        .chain(items.iter().map(|item| &item.variable))
+        .map(|var| var.id.to_uppercase())
+        .collect();
+
+    items
+        .into_iter()
+        .map(|item| {
+            let decision = options.decisions.get(&item.variable.id).copied();
+            let strategy = decision.unwrap_or(options.strategy);
+
+            let updated = |existing: &EnvironmentVariable, value: &str| {
+                let mut variable = existing.clone();
+                variable.value = value.to_string();
+                variable.expandable |= item.variable.expandable;
+                if item.variable.remark.is_some() {
+                    variable.remark = item.variable.remark.clone();
+                }
+                Action::Update { id: existing.id.clone(), variable }
+            };
+            let mut keep_both = |variable: &EnvironmentVariable| {
+                let mut renamed = variable.clone();
+                renamed.name = suffixed_name(&variable.name, &variable.var_type, &mut taken);
+                renamed.id = format!("{}_{}", renamed.var_type, renamed.name);
+                Action::Add(renamed)
+            };
+
+            let (strategy, action) = match (item.status, item.existing.as_ref()) {
+                (ImportStatus::New, _) | (_, None) => {
+                    if decision == Some(ImportStrategy::Skip) {
+                        ("skip", Action::Skip("已选择不导入".to_string()))
+                    } else {
+                        ("add", Action::Add(item.variable.clone()))
+                    }
+                }
+                (ImportStatus::Identical, Some(_)) => ("skip", Action::Skip("值与现有变量相同".to_string())),
+                (status, Some(existing)) => {
+                    let action = match strategy {
+                        ImportStrategy::Skip => Action::Skip("保留现有的值".to_string()),
+                        ImportStrategy::KeepBoth => keep_both(&item.variable),
+                        ImportStrategy::Merge => match &item.merged_value {
+                            Some(merged) => updated(existing, merged),
+                            None if is_path_like(&existing.name, &existing.value) => {
+                                Action::Skip("现有值已包含所有条目".to_string())
+                            }
+                            None => Action::Reject(format!("{} 不是 PATH 类变量，无法合并", existing.name)),
+                        },
+                        // 受保护的系统变量只允许追加条目
+                        ImportStrategy::Overwrite if status == ImportStatus::Protected => {
+                            Action::Reject(format!("{} 是受保护的系统变量，不能覆盖", existing.name))
+                        }
+                        ImportStrategy::Overwrite => updated(existing, &item.variable.value),
+                    };
+                    (strategy_name(strategy), action)
+                }
+            };
+            PlannedImport { item, strategy, action }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn env_map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn planned(incoming: &[EnvironmentVariable], current: &[EnvironmentVariable], options: &ImportOptions) -> Vec<PlannedImport> {
+        let items = preview(incoming, current, &env_map(&[("JAVA_HOME", r"C:\jdk")]), ReferenceSyntax::Windows);
+        plan(items, current, options)
+    }
+
+    fn describe(action: &Action) -> String {
+        match action {
+            Action::Add(variable) => format!("add {}={}", variable.name, variable.value),
+            Action::Update { id, variable } => format!("update {}={}", id, variable.value),
+            Action::Skip(_) => "skip".to_string(),
+            Action::Reject(_) => "reject".to_string(),
+        }
+    }
+
+    #[test]
+    fn previews_statuses_and_merged_path_values() {
+        let current = vec![
+            var("user", "SAME", "1"),
+            var("user", "Path", r"C:\jdk\bin\;C:\tools"),
+            var("system", "PATH", r"C:\Windows"),
+        ];
+        let incoming = vec![
+            var("user", "NEW", "x"),
+            var("user", "same", "1"),
+            var("user", "PATH", r"%JAVA_HOME%\bin;c:\TOOLS;C:\extra"),
+            var("system", "Path", r"C:\Windows;C:\more"),
+        ];
+        let items = preview(&incoming, &current, &env_map(&[("JAVA_HOME", r"C:\jdk")]), ReferenceSyntax::Windows);
+        let statuses: Vec<ImportStatus> = items.iter().map(|item| item.status).collect();
+        assert_eq!(
+            statuses,
+            vec![ImportStatus::New, ImportStatus::Identical, ImportStatus::Conflicting, ImportStatus::Protected]
+        );
+        assert_eq!(items[2].merged_value.as_deref(), Some(r"C:\jdk\bin\;C:\tools;C:\extra"));
+        assert_eq!(items[3].merged_value.as_deref(), Some(r"C:\Windows;C:\more"));
+    }
+
+    #[test]
+    fn applies_strategies_and_per_variable_decisions() {
+        let current = vec![var("user", "A", "old"), var("user", "A_IMPORTED", "taken"), var("system", "PATH", r"C:\Windows")];
+        let incoming = vec![
+            var("user", "A", "new"),
+            var("system", "PATH", r"C:\Windows;C:\more"),
+            var("user", "B", "fresh"),
+        ];
+
+        let overwrite = ImportOptions { strategy: ImportStrategy::Overwrite, ..ImportOptions::default() };
+        let actions: Vec<String> = planned(&incoming, &current, &overwrite).iter().map(|p| describe(&p.action)).collect();
+        assert_eq!(actions, vec!["update user_A=new", "reject", "add B=fresh"]);
+
+        let merge = ImportOptions { strategy: ImportStrategy::Merge, ..ImportOptions::default() };
+        let actions: Vec<String> = planned(&incoming, &current, &merge).iter().map(|p| describe(&p.action)).collect();
+        assert_eq!(actions, vec!["reject", r"update system_PATH=C:\Windows;C:\more", "add B=fresh"]);
+
+        let keep_both = ImportOptions {
+            strategy: ImportStrategy::KeepBoth,
+            decisions: HashMap::from([("user_B".to_string(), ImportStrategy::Skip), ("system_PATH".to_string(), ImportStrategy::Skip)]),
+        };
+        let plans = planned(&incoming, &current, &keep_both);
+        let actions: Vec<String> = plans.iter().map(|p| describe(&p.action)).collect();
+        assert_eq!(actions, vec!["add A_IMPORTED_2=new", "skip", "skip"]);
+        assert_eq!(plans[0].strategy, "keep-both");
+    }
+
+    #[test]
+    fn keeps_the_expandable_type_on_update() {
+        let current = vec![var("user", "Path", r"C:\a")];
+        let mut incoming = var("user", "Path", r"%JAVA_HOME%\bin");
+        incoming.expandable = true;
+        let overwrite = ImportOptions { strategy: ImportStrategy::Overwrite, ..ImportOptions::default() };
+        match &planned(&[incoming], &current, &overwrite)[0].action {
+            Action::Update { variable, .. } => assert!(variable.expandable),
+            other => panic!("unexpected action {}", describe(other)),
+        }
+    }
+}
@@ -0,0 +1,999 @@
+// Importers for our JSON export, dotenv, .reg files, shell and PowerShell scripts and docker env-files
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::bundle;
+use crate::commands::EnvironmentVariable;
+use crate::export_schema;
+use crate::validation::{self, Severity};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportFormat {
+    Json,
+    Dotenv,
+    Reg,
+    // bash/zsh 的 export 语句
+    Shell,
+    // $env: 赋值和 [Environment]::SetEnvironmentVariable
+    Powershell,
+    // docker --env-file，值不做任何引号处理
+    Docker,
+}
+
+// 解析诊断，line 从 1 开始，0 表示整个文件
+#[derive(Debug, Serialize, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ParsedImport {
+    pub format: ImportFormat,
+    pub variables: Vec<EnvironmentVariable>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedImport {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    variables: Vec<EnvironmentVariable>,
+    // 大写 ID -> (变量下标, 所在行)
+    seen: HashMap<String, (usize, usize)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Collector {
+    fn report(&mut self, line: usize, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic { line, severity, message });
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.report(line, Severity::Error, message);
+    }
+
+    fn warn(&mut self, line: usize, message: String) {
+        self.report(line, Severity::Warning, message);
+    }
+
+    fn push(&mut self, line: usize, scope: &str, name: &str, value: String) {
+        self.push_typed(line, scope, name, value, false);
+    }
+
+    // expandable 为 true 表示值来自 REG_EXPAND_SZ，写入时保留该类型
+    fn push_typed(&mut self, line: usize, scope: &str, name: &str, value: String, expandable: bool) {
+        self.push_variable(line, EnvironmentVariable {
+            id: format!("{}_{}", scope, name),
+            name: name.to_string(),
+            value,
+            var_type: scope.to_string(),
+            remark: None,
+            created_at: 0,
+            updated_at: 0,
+            is_valid: true,
+            sensitive: false,
+            expandable,
+        });
+    }
+
+    // 同一作用域内重复定义时保留后出现的值
+    fn push_variable(&mut self, line: usize, var: EnvironmentVariable) {
+        // 只关心会导致写入失败的问题，命名风格等提示在写入时再给出
+        let errors: Vec<String> = validation::check_assignment(&var.name, &var.value, &[])
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .map(|finding| finding.message)
+            .collect();
+        if !errors.is_empty() {
+            for message in errors {
+                self.error(line, message);
+            }
+            return;
+        }
+
+        let key = var.id.to_uppercase();
+        match self.seen.get(&key).copied() {
+            Some((index, previous)) => {
+                self.warn(line, format!("{} 与第 {} 行重复，使用本行的值", var.name, previous));
+                self.variables[index] = var;
+                self.seen.insert(key, (index, line));
+            }
+            None => {
+                self.seen.insert(key, (self.variables.len(), line));
+                self.variables.push(var);
+            }
+        }
+    }
+
+    fn finish(self, format: ImportFormat) -> ParsedImport {
+        ParsedImport {
+            format,
+            variables: self.variables,
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+// 按 BOM 识别 UTF-16（regedit 导出的文件）和 UTF-8
+pub fn decode(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], little: bool| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| if little { u16::from_le_bytes([pair[0], pair[1]]) } else { u16::from_be_bytes([pair[0], pair[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, true),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, false),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn content_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content.lines().enumerate().map(|(i, line)| (i + 1, line.trim()))
+}
+
+// 根据内容和文件名判断格式
+pub fn detect(content: &str, file_name: &str) -> ImportFormat {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with("Windows Registry Editor") || trimmed.starts_with("REGEDIT4") {
+        return ImportFormat::Reg;
+    }
+    if trimmed.starts_with('{') {
+        return ImportFormat::Json;
+    }
+
+    let lower_name = file_name.to_lowercase();
+    let extension = Path::new(&lower_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "reg" => return ImportFormat::Reg,
+        "json" => return ImportFormat::Json,
+        "ps1" => return ImportFormat::Powershell,
+        "sh" | "bash" | "zsh" => return ImportFormat::Shell,
+        _ => {}
+    }
+
+    let statements: Vec<&str> = content_lines(content)
+        .map(|(_, line)| line)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let lower = |line: &str| line.to_lowercase();
+    if statements.iter().any(|line| {
+        let line = lower(line);
+        line.starts_with("$env:") || line.starts_with("${env:") || line.starts_with("[environment]::")
+    }) {
+        return ImportFormat::Powershell;
+    }
+    if trimmed.starts_with("#!") {
+        return ImportFormat::Shell;
+    }
+    if lower_name.contains("docker") || extension == "list" {
+        return ImportFormat::Docker;
+    }
+    // docker env-file 允许只写变量名，表示从宿主环境取值
+    if statements.iter().any(|line| is_identifier(line)) {
+        return ImportFormat::Docker;
+    }
+    ImportFormat::Dotenv
+}
+
+// 解析导入内容，format 为空时自动识别
+pub fn parse(content: &str, file_name: &str, format: Option<ImportFormat>) -> ParsedImport {
+    let format = format.unwrap_or_else(|| detect(content, file_name));
+    let collector = match format {
+        ImportFormat::Json => parse_json(content),
+        ImportFormat::Dotenv => parse_dotenv(content),
+        ImportFormat::Reg => parse_reg(content),
+        ImportFormat::Shell => parse_shell(content),
+        ImportFormat::Powershell => parse_powershell(content),
+        ImportFormat::Docker => parse_docker(content),
+    };
+    collector.finish(format)
+}
+
+// 读取并解析导入文件；加密的导出文件需要提供密码
+pub fn parse_file(path: &Path, format: Option<ImportFormat>, password: Option<&str>) -> Result<ParsedImport, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let content = decode(&bytes);
+
+    if bundle::is_bundle(&content) {
+        let password = password.ok_or_else(|| "文件已加密，请提供密码".to_string())?;
+        let plaintext = bundle::decrypt(&content, password)?;
+        return Ok(parse(&plaintext, &file_name, Some(ImportFormat::Json)));
+    }
+    Ok(parse(&content, &file_name, format))
+}
+
+fn parse_json(content: &str) -> Collector {
+    let mut collector = Collector::default();
+    let import_data: serde_json::Value = match serde_json::from_str(content) {
+        Ok(data) => data,
+        Err(e) => {
+            collector.error(e.line(), format!("Failed to parse JSON: {}", e));
+            return collector;
+        }
+    };
+
+    // 按导出格式的版本校验，旧版本迁移到当前版本
+    let loaded = match export_schema::load(import_data) {
+        Ok(loaded) => loaded,
+        Err(issues) => {
+            for issue in issues {
+                collector.error(0, format!("{}: {}", issue.path, issue.message));
+            }
+            return collector;
+        }
+    };
+    if let Some(version) = loaded.migrated_from {
+        collector.report(
+            0,
+            Severity::Info,
+            format!("文件为格式版本 {}，已迁移到版本 {}", version, export_schema::CURRENT_VERSION),
+        );
+    }
+    for var in loaded.document.variables {
+        if var.redacted {
+            collector.warn(0, format!("{} 在导出时已脱敏，没有可导入的值，已跳过", var.name));
+            continue;
+        }
+        collector.push_variable(0, var.into_variable());
+    }
+    collector
+}
+
+// 读取引号内的内容，返回值和结束引号之后的部分；没有结束引号时返回 None
+fn take_dotenv_quoted(text: &str, quote: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            return Some((value, &text[i + 1..]));
+        }
+        if quote == '"' && c == '\\' {
+            match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, escaped @ ('\\' | '"' | '$'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => value.push('\\'),
+            }
+            continue;
+        }
+        value.push(c);
+    }
+    None
+}
+
+fn parse_dotenv(content: &str) -> Collector {
+    let mut collector = Collector::default();
+    let lines: Vec<&str> = content.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line_no = i + 1;
+        let line = lines[i].trim();
+        i += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+        let Some((name, rest)) = line.split_once('=') else {
+            collector.error(line_no, format!("缺少 '=': {}", line));
+            continue;
+        };
+        let name = name.trim();
+        if !is_identifier(name) {
+            collector.error(line_no, format!("'{}' 不是合法的变量名", name));
+            continue;
+        }
+
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                // 引号内的值可以跨行
+                let mut text = rest[1..].to_string();
+                loop {
+                    if let Some((value, tail)) = take_dotenv_quoted(&text, quote) {
+                        let tail = tail.trim();
+                        if !tail.is_empty() && !tail.starts_with('#') {
+                            collector.warn(line_no, format!("引号之后的内容已忽略: {}", tail));
+                        }
+                        break Some(value);
+                    }
+                    if i >= lines.len() {
+                        collector.error(line_no, format!("{} 的引号没有结束", name));
+                        break None;
+                    }
+                    text.push('\n');
+                    text.push_str(lines[i]);
+                    i += 1;
+                }
+            }
+            _ => {
+                let end = rest.find(" #").unwrap_or(rest.len());
+                Some(rest[..end].trim_end().to_string())
+            }
+        };
+        if let Some(value) = value {
+            collector.push(line_no, "user", name, value);
+        }
+    }
+    collector
+}
+
+fn parse_docker(content: &str) -> Collector {
+    let mut collector = Collector::default();
+    for (line_no, raw) in content.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        let line = raw.trim_start();
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((name, value)) => {
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    collector.error(line_no, format!("'{}' 不是合法的变量名", name));
+                    continue;
+                }
+                collector.push(line_no, "user", name, value.to_string());
+            }
+            None => {
+                let name = line.trim();
+                if name.contains(char::is_whitespace) {
+                    collector.error(line_no, format!("缺少 '=': {}", name));
+                } else {
+                    collector.warn(line_no, format!("{} 没有值，docker 会从宿主环境读取，已跳过", name));
+                }
+            }
+        }
+    }
+    collector
+}
+
+// 单条语句的解析结果
+enum Statement {
+    Assign {
+        scope: &'static str,
+        name: String,
+        value: String,
+        notes: Vec<String>,
+    },
+    Skip(String),
+}
+
+// 语句解析失败；Unterminated 表示引号没有结束，可以拼接下一行后重试
+enum Failure {
+    Unterminated(String),
+    Invalid(String),
+}
+
+impl Failure {
+    fn prefixed(self, name: &str) -> Self {
+        match self {
+            Failure::Unterminated(message) => Failure::Unterminated(format!("{}: {}", name, message)),
+            Failure::Invalid(message) => Failure::Invalid(format!("{}: {}", name, message)),
+        }
+    }
+}
+
+// 逐行解析语句；引号跨行时把后续行拼接到同一条语句中，诊断记在语句的第一行
+fn parse_statements(content: &str, mut parse: impl FnMut(&str) -> Result<Statement, Failure>) -> Collector {
+    let mut collector = Collector::default();
+    let lines: Vec<&str> = content.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line_no = i + 1;
+        let mut statement = lines[i].trim_start().to_string();
+        i += 1;
+        if statement.trim().is_empty() || statement.starts_with('#') {
+            continue;
+        }
+
+        let outcome = loop {
+            match parse(&statement) {
+                Err(Failure::Unterminated(_)) if i < lines.len() => {
+                    statement.push('\n');
+                    statement.push_str(lines[i]);
+                    i += 1;
+                }
+                outcome => break outcome,
+            }
+        };
+        match outcome {
+            Ok(Statement::Assign { scope, name, value, notes }) => {
+                for note in notes {
+                    collector.warn(line_no, note);
+                }
+                collector.push(line_no, scope, &name, value);
+            }
+            Ok(Statement::Skip(message)) => collector.warn(line_no, message),
+            Err(Failure::Unterminated(message) | Failure::Invalid(message)) => collector.error(line_no, message),
+        }
+    }
+    collector
+}
+
+// 按 POSIX shell 规则读取一个单词，返回值、是否包含 $ 或 ` 展开，以及剩余部分
+fn take_shell_word(text: &str) -> Result<(String, bool, &str), Failure> {
+    let mut value = String::new();
+    let mut expansion = false;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() || c == ';' => return Ok((value, expansion, &text[i..])),
+            '\'' => loop {
+                match chars.next() {
+                    Some((_, '\'')) => break,
+                    Some((_, c)) => value.push(c),
+                    None => return Err(Failure::Unterminated("单引号没有结束".to_string())),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped @ ('$' | '`' | '"' | '\\'))) => value.push(escaped),
+                        Some((_, '\n')) => {}
+                        Some((_, other)) => {
+                            value.push('\\');
+                            value.push(other);
+                        }
+                        None => return Err(Failure::Unterminated("双引号没有结束".to_string())),
+                    },
+                    Some((_, c)) => {
+                        if c == '$' || c == '`' {
+                            expansion = true;
+                        }
+                        value.push(c);
+                    }
+                    None => return Err(Failure::Unterminated("双引号没有结束".to_string())),
+                }
+            },
+            // 行尾的 \ 表示续行
+            '\\' => match chars.next() {
+                Some((_, '\n')) => {}
+                Some((_, escaped)) => value.push(escaped),
+                None => return Err(Failure::Unterminated("续行没有内容".to_string())),
+            },
+            '$' | '`' => {
+                expansion = true;
+                value.push(c);
+            }
+            c => value.push(c),
+        }
+    }
+    Ok((value, expansion, ""))
+}
+
+fn shell_statement(line: &str) -> Result<Statement, Failure> {
+    let statement = ["export ", "declare -x ", "typeset -x "]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(str::trim_start);
+    let exported = statement.is_some();
+    let statement = statement.unwrap_or(line);
+
+    let assignment = statement
+        .split_once('=')
+        .filter(|(name, _)| is_identifier(name));
+    let Some((name, rest)) = assignment else {
+        return Ok(Statement::Skip(if exported && statement.split_whitespace().all(is_identifier) {
+            format!("export {} 没有赋值，已跳过", statement.trim())
+        } else {
+            format!("无法识别的语句，已跳过: {}", line.trim())
+        }));
+    };
+
+    let (value, expansion, tail) = take_shell_word(rest).map_err(|failure| failure.prefixed(name))?;
+    let mut notes = Vec::new();
+    if expansion {
+        notes.push(format!("{} 的值包含 shell 展开，按原样导入", name));
+    }
+    let tail = tail.trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+    if !tail.is_empty() && !tail.starts_with('#') {
+        notes.push(format!("同一行的其余内容已忽略: {}", tail));
+    }
+    Ok(Statement::Assign {
+        scope: "user",
+        name: name.to_string(),
+        value,
+        notes,
+    })
+}
+
+fn parse_shell(content: &str) -> Collector {
+    parse_statements(content, shell_statement)
+}
+
+// 读取 PowerShell 字符串字面量，返回值、是否包含 $ 展开，以及剩余部分
+fn take_powershell_string(text: &str) -> Result<(String, bool, &str), Failure> {
+    let text = text.trim_start();
+    let mut chars = text.char_indices().peekable();
+    let quote = match chars.next() {
+        Some((_, quote @ ('\'' | '"'))) => quote,
+        _ => return Err(Failure::Invalid("值必须是字符串字面量".to_string())),
+    };
+
+    let mut value = String::new();
+    let mut expansion = false;
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            // 连续两个引号表示引号本身
+            if chars.peek().map(|&(_, next)| next) == Some(quote) {
+                chars.next();
+                value.push(quote);
+                continue;
+            }
+            return Ok((value, expansion, &text[i + 1..]));
+        }
+        if quote == '"' && c == '`' {
+            match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, '0')) => value.push('\0'),
+                Some((_, other)) => value.push(other),
+                None => break,
+            }
+            continue;
+        }
+        if quote == '"' && c == '$' {
+            expansion = true;
+        }
+        value.push(c);
+    }
+    Err(Failure::Unterminated("字符串没有结束".to_string()))
+}
+
+fn is_null(text: &str) -> bool {
+    text.trim_start().to_lowercase().starts_with("$null")
+}
+
+// $env:NAME 或 ${env:NAME}，返回变量名和剩余部分
+fn powershell_env_target(line: &str) -> Option<Result<(String, &str), Failure>> {
+    let lower = line.to_lowercase();
+    if lower.starts_with("$env:") {
+        let rest = &line[5..];
+        let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        return Some(Ok((rest[..end].to_string(), &rest[end..])));
+    }
+    if !lower.starts_with("${env:") {
+        return None;
+    }
+
+    let rest = &line[6..];
+    let mut name = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '`' => {
+                if let Some((_, escaped)) = chars.next() {
+                    name.push(escaped);
+                }
+            }
+            '}' => return Some(Ok((name, &rest[i + 1..]))),
+            c => name.push(c),
+        }
+    }
+    Some(Err(Failure::Invalid("${env:...} 缺少 '}'".to_string())))
+}
+
+fn powershell_statement(line: &str) -> Result<Statement, Failure> {
+    let assign = |scope: &'static str, name: String, value: String, expansion: bool, mut notes: Vec<String>| {
+        if expansion {
+            notes.push(format!("{} 的值包含变量展开，按原样导入", name));
+        }
+        Statement::Assign { scope, name, value, notes }
+    };
+
+    // $env:NAME = '...' 或 ${env:NAME} = '...'
+    if let Some(target) = powershell_env_target(line) {
+        let (name, rest) = target?;
+        let Some(rest) = rest.trim_start().strip_prefix('=') else {
+            return Ok(Statement::Skip(format!("无法识别的语句，已跳过: {}", line.trim())));
+        };
+        if is_null(rest) {
+            return Ok(Statement::Skip(format!("删除 {} 的语句已跳过", name)));
+        }
+        let (value, expansion, _) = take_powershell_string(rest).map_err(|failure| failure.prefixed(&name))?;
+        return Ok(assign("user", name, value, expansion, Vec::new()));
+    }
+
+    // [Environment]::SetEnvironmentVariable('NAME', 'value', 'User')
+    const SET_CALL: &str = "[environment]::setenvironmentvariable(";
+    if !line.to_lowercase().starts_with(SET_CALL) {
+        return Ok(Statement::Skip(format!("无法识别的语句，已跳过: {}", line.trim())));
+    }
+    let (name, _, rest) = take_powershell_string(&line[SET_CALL.len()..])?;
+    let missing_comma = || Failure::Invalid("参数之间缺少 ','".to_string());
+    let rest = rest.trim_start().strip_prefix(',').ok_or_else(missing_comma)?;
+    if is_null(rest) {
+        return Ok(Statement::Skip(format!("删除 {} 的语句已跳过", name)));
+    }
+    let (value, expansion, rest) = take_powershell_string(rest).map_err(|failure| failure.prefixed(&name))?;
+    let target = match rest.trim_start().strip_prefix(',') {
+        Some(rest) => take_powershell_string(rest)?.0,
+        None => "Process".to_string(),
+    };
+
+    let mut notes = Vec::new();
+    let scope = match target.to_lowercase().as_str() {
+        "user" => "user",
+        "machine" => "system",
+        _ => {
+            notes.push(format!("{} 的作用域 {} 只影响当前进程，按用户变量导入", name, target));
+            "user"
+        }
+    };
+    Ok(assign(scope, name, value, expansion, notes))
+}
+
+fn parse_powershell(content: &str) -> Collector {
+    parse_statements(content, powershell_statement)
+}
+
+// 读取 .reg 中带引号的字符串（只转义 \\ 和 \"），返回值和剩余部分
+fn take_reg_string(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &text[i + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+// 行内是否有未结束的字符串
+fn reg_string_open(line: &str) -> bool {
+    if line.starts_with(';') {
+        return false;
+    }
+    let mut open = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => open = !open,
+            '\\' if open => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    open
+}
+
+// 解码 hex(2)（REG_EXPAND_SZ）：5.00 版本为 UTF-16LE，REGEDIT4 为单字节编码，均以 0 结尾
+fn decode_expand_string(hex: &str, unicode: bool) -> Result<String, String> {
+    let bytes = hex
+        .split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("无效的十六进制字节 '{}'", byte)))
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    if unicode {
+        if bytes.len() % 2 != 0 {
+            return Err("hex(2) 数据的字节数不是偶数".to_string());
+        }
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        String::from_utf16(&units).map_err(|_| "hex(2) 数据不是有效的 UTF-16".to_string())
+    } else {
+        let bytes: Vec<u8> = bytes.into_iter().take_while(|&byte| byte != 0).collect();
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn registry_scope(key: &str) -> Option<&'static str> {
+    let key = key.to_uppercase().replace("HKEY_CURRENT_USER", "HKCU").replace("HKEY_LOCAL_MACHINE", "HKLM");
+    match key.as_str() {
+        "HKCU\\ENVIRONMENT" => Some("user"),
+        "HKLM\\SYSTEM\\CURRENTCONTROLSET\\CONTROL\\SESSION MANAGER\\ENVIRONMENT" => Some("system"),
+        _ => None,
+    }
+}
+
+fn parse_reg(content: &str) -> Collector {
+    let mut collector = Collector::default();
+
+    // 合并续行：十六进制数据以 \ 结尾折行，字符串值中的换行原样保留
+    let mut logical: Vec<(usize, String)> = Vec::new();
+    let mut open: Option<bool> = None;
+    for (i, raw) in content.lines().enumerate() {
+        match (open, logical.last_mut()) {
+            (Some(true), Some((_, last))) => {
+                last.push('\n');
+                last.push_str(raw);
+            }
+            (Some(false), Some((_, last))) => {
+                last.pop();
+                last.push_str(raw.trim());
+            }
+            _ => logical.push((i + 1, raw.trim().to_string())),
+        }
+        let last = logical.last().map(|(_, line)| line.as_str()).unwrap_or_default();
+        open = if reg_string_open(last) {
+            Some(true)
+        } else if last.ends_with('\\') && last.contains("=hex") {
+            Some(false)
+        } else {
+            None
+        };
+    }
+
+    let mut lines = logical.into_iter().filter(|(_, line)| !line.is_empty() && !line.starts_with(';'));
+    let unicode = match lines.next() {
+        Some((_, header)) if header == "Windows Registry Editor Version 5.00" => true,
+        Some((_, header)) if header == "REGEDIT4" => false,
+        Some((line_no, header)) => {
+            collector.error(line_no, format!("无法识别的 .reg 文件头: {}", header));
+            return collector;
+        }
+        None => {
+            collector.error(0, ".reg 文件为空".to_string());
+            return collector;
+        }
+    };
+
+    let mut scope: Option<&str> = None;
+    for (line_no, line) in lines {
+        if let Some(key) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            scope = None;
+            if key.starts_with('-') {
+                collector.warn(line_no, format!("删除注册表项的语句已跳过: {}", key));
+            } else {
+                scope = registry_scope(key);
+                if scope.is_none() {
+                    collector.warn(line_no, format!("{} 不是环境变量所在的注册表项，其中的值已跳过", key));
+                }
+            }
+            continue;
+        }
+
+        let Some(scope) = scope else {
+            continue;
+        };
+        if line.starts_with("@=") {
+            continue;
+        }
+        let Some((name, rest)) = take_reg_string(&line) else {
+            collector.error(line_no, format!("无法解析的值: {}", line));
+            continue;
+        };
+        let Some(data) = rest.trim_start().strip_prefix('=') else {
+            collector.error(line_no, format!("{} 缺少 '='", name));
+            continue;
+        };
+        let data = data.trim();
+
+        if data == "-" {
+            collector.warn(line_no, format!("删除 {} 的语句已跳过", name));
+        } else if data.starts_with('"') {
+            match take_reg_string(data) {
+                Some((value, _)) => collector.push(line_no, scope, &name, value),
+                None => collector.error(line_no, format!("{} 的字符串没有结束", name)),
+            }
+        } else if let Some(hex) = data.strip_prefix("hex(2):") {
+            match decode_expand_string(hex, unicode) {
+                Ok(value) => collector.push_typed(line_no, scope, &name, value, true),
+                Err(message) => collector.error(line_no, format!("{}: {}", name, message)),
+            }
+        } else {
+            collector.warn(line_no, format!("{} 不是字符串类型的值，已跳过", name));
+        }
+    }
+    collector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16_hex(value: &str) -> String {
+        value
+            .encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn values(parsed: &ParsedImport) -> Vec<(&str, &str, &str)> {
+        parsed
+            .variables
+            .iter()
+            .map(|v| (v.var_type.as_str(), v.name.as_str(), v.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_dotenv_quotes_comments_and_duplicates() {
+        let content = "# comment\nexport A=plain # trailing\nB=\"line\\nnext \\\"q\\\"\"\nC='single $HOME'\nD=\"multi\nline\"\nA=again\n";
+        let parsed = parse(content, "app.env", None);
+        assert_eq!(parsed.format, ImportFormat::Dotenv);
+        assert_eq!(
+            values(&parsed),
+            vec![
+                ("user", "A", "again"),
+                ("user", "B", "line\nnext \"q\""),
+                ("user", "C", "single $HOME"),
+                ("user", "D", "multi\nline"),
+            ]
+        );
+        assert!(!parsed.has_errors());
+        assert!(parsed.diagnostics.iter().any(|d| d.line == 7 && d.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn reports_dotenv_errors_with_line_numbers() {
+        let parsed = parse("X=1\nbad line\n1X=2\nY='unterminated\n", "a.env", Some(ImportFormat::Dotenv));
+        assert_eq!(values(&parsed), vec![("user", "X", "1")]);
+        let lines: Vec<usize> = parsed.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert!(parsed.has_errors());
+    }
+
+    #[test]
+    fn parses_reg_strings_and_expandable_values() {
+        let content = format!(
+            "Windows Registry Editor Version 5.00\r\n\r\n[HKEY_CURRENT_USER\\Environment]\r\n\"HOME_DIR\"=\"C:\\\\Users\\\\me\"\r\n\"Path\"=hex(2):{}\r\n\r\n[HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment]\r\n\"OS\"=\"Windows_NT\"\r\n",
+            utf16_hex("%USERPROFILE%\\bin")
+        );
+        let parsed = parse(&content, "env.reg", None);
+        assert_eq!(parsed.format, ImportFormat::Reg);
+        assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+        assert_eq!(
+            values(&parsed),
+            vec![
+                ("user", "HOME_DIR", r"C:\Users\me"),
+                ("user", "Path", r"%USERPROFILE%\bin"),
+                ("system", "OS", "Windows_NT"),
+            ]
+        );
+        let expandable: Vec<bool> = parsed.variables.iter().map(|v| v.expandable).collect();
+        assert_eq!(expandable, vec![false, true, false]);
+    }
+
+    #[test]
+    fn joins_reg_hex_continuation_lines() {
+        let hex = utf16_hex("%JAVA_HOME%\\bin");
+        let (head, tail) = hex.split_at(hex.len() / 2 / 3 * 3);
+        let content = format!(
+            "Windows Registry Editor Version 5.00\n[HKEY_CURRENT_USER\\Environment]\n\"J\"=hex(2):{}\\\n  {}\n",
+            head,
+            tail.trim_start_matches(',')
+        );
+        let parsed = parse(&content, "env.reg", None);
+        assert_eq!(values(&parsed), vec![("user", "J", r"%JAVA_HOME%\bin")]);
+        assert!(parsed.variables[0].expandable);
+    }
+
+    #[test]
+    fn skips_other_reg_keys_and_value_types() {
+        let content = "Windows Registry Editor Version 5.00\n[HKEY_CURRENT_USER\\Software\\App]\n\"X\"=\"1\"\n[HKEY_CURRENT_USER\\Environment]\n\"N\"=dword:00000001\n\"D\"=-\n\"S\"=\"ok\"\n";
+        let parsed = parse(content, "env.reg", None);
+        assert_eq!(values(&parsed), vec![("user", "S", "ok")]);
+        assert_eq!(parsed.diagnostics.iter().filter(|d| d.severity == Severity::Warning).count(), 3);
+        assert!(!parsed.has_errors());
+
+        let parsed = parse("not a registry file", "env.reg", Some(ImportFormat::Reg));
+        assert!(parsed.has_errors());
+    }
+
+    #[test]
+    fn detects_formats_from_content_and_file_name() {
+        assert_eq!(detect("REGEDIT4\n", "x.txt"), ImportFormat::Reg);
+        assert_eq!(detect("{\"variables\": []}", "x.txt"), ImportFormat::Json);
+        assert_eq!(detect("A=1", "profile.ps1"), ImportFormat::Powershell);
+        assert_eq!(detect("$env:A = '1'", "vars.txt"), ImportFormat::Powershell);
+        assert_eq!(detect("#!/bin/sh\nexport A=1", "vars"), ImportFormat::Shell);
+        assert_eq!(detect("A=1\nHOST_VAR\n", "vars"), ImportFormat::Docker);
+        assert_eq!(detect("A=1", "docker.env"), ImportFormat::Docker);
+        assert_eq!(detect("A=1", ".env"), ImportFormat::Dotenv);
+    }
+
+    #[test]
+    fn decodes_utf16_and_utf8_byte_order_marks() {
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain("A=é".encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        assert_eq!(decode(&utf16), "A=é");
+        assert_eq!(decode(&[0xEF, 0xBB, 0xBF, b'A']), "A");
+        assert_eq!(decode(b"A"), "A");
+    }
+
+    #[test]
+    fn parses_shell_exports_across_lines() {
+        let content = "#!/bin/bash\nexport A='x y'; echo hi\ndeclare -x B=\"multi\nline $HOME\"\nexport C\nalias ll='ls -l'\nD=plain\\ word\n";
+        let parsed = parse(content, "env.sh", None);
+        assert_eq!(parsed.format, ImportFormat::Shell);
+        assert_eq!(
+            values(&parsed),
+            vec![("user", "A", "x y"), ("user", "B", "multi\nline $HOME"), ("user", "D", "plain word")]
+        );
+        let lines: Vec<usize> = parsed.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![2, 3, 5, 6]);
+        assert!(!parsed.has_errors());
+
+        let parsed = parse("export A='open\n", "env.sh", None);
+        assert!(parsed.has_errors());
+    }
+
+    #[test]
+    fn parses_powershell_assignments_and_scopes() {
+        let content = "$env:A = 'it''s'\n${env:ProgramFiles(x86)} = \"C:\\x`t\"\n[Environment]::SetEnvironmentVariable('B', '1', 'Machine')\n[Environment]::SetEnvironmentVariable('C', '2')\n$env:D = $null\n";
+        let parsed = parse(content, "env.ps1", None);
+        assert_eq!(
+            values(&parsed),
+            vec![
+                ("user", "A", "it's"),
+                ("user", "ProgramFiles(x86)", "C:\\x\t"),
+                ("system", "B", "1"),
+                ("user", "C", "2"),
+            ]
+        );
+        let lines: Vec<usize> = parsed.diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![4, 5]);
+
+        let parsed = parse("$env:A = Get-Value\n", "env.ps1", None);
+        assert!(parsed.has_errors());
+    }
+
+    #[test]
+    fn keeps_docker_values_verbatim() {
+        let parsed = parse("A=\"quoted\" # not a comment\nHOST_ONLY\nbad name=1\n", "docker.env", None);
+        assert_eq!(parsed.format, ImportFormat::Docker);
+        assert_eq!(values(&parsed), vec![("user", "A", "\"quoted\" # not a comment")]);
+        let severities: Vec<Severity> = parsed.diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, vec![Severity::Warning, Severity::Error]);
+    }
+
+    #[test]
+    fn migrates_json_exports_and_rejects_illegal_names() {
+        let content = r#"{"version": "1.0", "variables": [
+            {"id": "x", "name": "A", "value": "1", "type": "user"},
+            {"id": "z", "name": "C=D", "value": "3", "type": "system"}
+        ]}"#;
+        let parsed = parse(content, "export.json", None);
+        assert_eq!(values(&parsed), vec![("user", "A", "1")]);
+        assert_eq!(parsed.variables[0].id, "user_A");
+        let severities: Vec<Severity> = parsed.diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, vec![Severity::Info, Severity::Error]);
+
+        let content = r#"{"version": "1.0", "variables": [{"name": "B", "value": "2", "type": "machine"}]}"#;
+        let parsed = parse(content, "export.json", None);
+        assert!(parsed.variables.is_empty());
+        assert!(parsed.diagnostics[0].message.starts_with("variables[0].type: "));
+    }
+}
@@ -0,0 +1,353 @@
+// Environment block and PATH length limit analysis
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::commands::EnvironmentVariable;
+use crate::expand::{Expander, ReferenceSyntax};
+use crate::validation::{Finding, Severity, MAX_VARIABLE_LENGTH};
+
+// 整个环境块的硬性上限（字符数）
+pub const ENVIRONMENT_BLOCK_LIMIT: usize = MAX_VARIABLE_LENGTH;
+// 部分旧工具只能处理约 2047 个字符的 PATH
+pub const LEGACY_PATH_LIMIT: usize = 2_047;
+// setx 会截断超过 1024 个字符的值
+pub const SETX_LIMIT: usize = 1_024;
+// 达到上限的 90% 时给出提醒
+const WARNING_RATIO: f64 = 0.9;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VariableSize {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub var_type: String,
+    // name=value 加结尾 NUL 的长度
+    #[serde(rename = "rawLength")]
+    pub raw_length: usize,
+    #[serde(rename = "expandedLength")]
+    pub expanded_length: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SizeReport {
+    pub variables: Vec<VariableSize>,
+    #[serde(rename = "blockRawLength")]
+    pub block_raw_length: usize,
+    #[serde(rename = "blockExpandedLength")]
+    pub block_expanded_length: usize,
+    #[serde(rename = "blockLimit")]
+    pub block_limit: usize,
+    pub findings: Vec<Finding>,
+}
+
+fn entry_length(name: &str, value: &str) -> usize {
+    name.chars().count() + value.chars().count() + 2
+}
+
+fn is_close(length: usize, limit: usize) -> bool {
+    length as f64 >= limit as f64 * WARNING_RATIO
+}
+
+// 进程环境块中的变量：同名变量与 get_all_env_vars_map 一致按系统变量优先，PATH 为系统 PATH 与用户 PATH 拼接
+fn block_entries(variables: &[EnvironmentVariable]) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+
+    let ordered = variables
+        .iter()
+        .filter(|var| var.var_type == "system")
+        .chain(variables.iter().filter(|var| var.var_type != "system"));
+    for var in ordered {
+        let key = var.name.to_uppercase();
+        match index.get(&key) {
+            Some(&i) if key == "PATH" => {
+                let joined = format!("{};{}", entries[i].1.trim_end_matches(';'), var.value);
+                entries[i].1 = joined;
+            }
+            Some(_) => {}
+            None => {
+                index.insert(key, entries.len());
+                entries.push((var.name.clone(), var.value.clone()));
+            }
+        }
+    }
+
+    entries
+}
+
+// 计算每个变量及整个环境块展开前后的长度，并检查各项限制
+pub fn analyze(variables: &[EnvironmentVariable], syntax: ReferenceSyntax) -> SizeReport {
+    let entries = block_entries(variables);
+    let env_map: HashMap<String, String> = entries
+        .iter()
+        .map(|(name, value)| (name.to_uppercase(), value.clone()))
+        .collect();
+    let mut expander = Expander::new(&env_map, syntax);
+    let mut findings = Vec::new();
+
+    let sizes: Vec<VariableSize> = variables
+        .iter()
+        .map(|var| {
+            let expanded = expander.expand_as(&var.name, &var.value).expanded;
+            VariableSize {
+                id: var.id.clone(),
+                name: var.name.clone(),
+                var_type: var.var_type.clone(),
+                raw_length: entry_length(&var.name, &var.value),
+                expanded_length: entry_length(&var.name, &expanded),
+            }
+        })
+        .collect();
+
+    for size in &sizes {
+        let length = size.raw_length.max(size.expanded_length);
+        if length > MAX_VARIABLE_LENGTH {
+            findings.push(Finding::new(
+                "limit.variable",
+                Severity::Error,
+                format!("{} 的长度 {} 超过 {} 个字符的上限", size.id, length, MAX_VARIABLE_LENGTH),
+            ));
+        }
+        let value_length = size.raw_length - size.name.chars().count() - 2;
+        if value_length > SETX_LIMIT {
+            findings.push(
+                Finding::new(
+                    "limit.setx",
+                    Severity::Warning,
+                    format!("{} 的值有 {} 个字符，使用 setx 修改时会被截断为 {} 个字符", size.id, value_length, SETX_LIMIT),
+                )
+                .with_suggestion("不要使用 setx 修改该变量".to_string()),
+            );
+        }
+    }
+
+    let mut block_raw_length = 1;
+    let mut block_expanded_length = 1;
+    for (name, value) in &entries {
+        let expanded = expander.expand_as(name, value).expanded;
+        block_raw_length += entry_length(name, value);
+        block_expanded_length += entry_length(name, &expanded);
+
+        if name.eq_ignore_ascii_case("PATH") {
+            let path_length = expanded.chars().count();
+            if path_length > LEGACY_PATH_LIMIT {
+                findings.push(
+                    Finding::new(
+                        "limit.path-legacy",
+                        Severity::Warning,
+                        format!("展开后的 PATH 有 {} 个字符，超过部分旧工具支持的 {} 个字符", path_length, LEGACY_PATH_LIMIT),
+                    )
+                    .with_suggestion("移除无效或重复的 PATH 条目".to_string()),
+                );
+            } else if is_close(path_length, LEGACY_PATH_LIMIT) {
+                findings.push(Finding::new(
+                    "limit.path-legacy",
+                    Severity::Info,
+                    format!("展开后的 PATH 有 {} 个字符，接近 {} 个字符的限制", path_length, LEGACY_PATH_LIMIT),
+                ));
+            }
+        }
+    }
+
+    if block_expanded_length > ENVIRONMENT_BLOCK_LIMIT {
+        findings.push(Finding::new(
+            "limit.block",
+            Severity::Error,
+            format!("环境块展开后有 {} 个字符，超过 {} 个字符的上限", block_expanded_length, ENVIRONMENT_BLOCK_LIMIT),
+        ));
+    } else if is_close(block_expanded_length, ENVIRONMENT_BLOCK_LIMIT) {
+        findings.push(Finding::new(
+            "limit.block",
+            Severity::Warning,
+            format!("环境块展开后有 {} 个字符，接近 {} 个字符的上限", block_expanded_length, ENVIRONMENT_BLOCK_LIMIT),
+        ));
+    }
+
+    SizeReport {
+        variables: sizes,
+        block_raw_length,
+        block_expanded_length,
+        block_limit: ENVIRONMENT_BLOCK_LIMIT,
+        findings,
+    }
+}
+
+// 模拟写入后的变量列表：old_name 为 None 时新增，否则替换（重命名时移除旧名称）
+pub fn project_assignment(
+    variables: &[EnvironmentVariable],
+    var_type: &str,
+    old_name: Option<&str>,
+    name: &str,
+    value: &str,
+) -> Vec<EnvironmentVariable> {
+    let scope = if var_type == "system" { "system" } else { "user" };
+    let mut projected: Vec<EnvironmentVariable> = variables
+        .iter()
+        .filter(|var| {
+            let replaced = old_name.unwrap_or(name);
+            !(var.var_type == scope && var.name.eq_ignore_ascii_case(replaced))
+        })
+        .cloned()
+        .collect();
+
+    let existing = variables
+        .iter()
+        .find(|var| var.var_type == scope && var.name.eq_ignore_ascii_case(old_name.unwrap_or(name)));
+    let mut variable = match existing {
+        Some(var) => var.clone(),
+        None => EnvironmentVariable {
+            id: format!("{}_{}", scope, name),
+            name: name.to_string(),
+            value: String::new(),
+            var_type: scope.to_string(),
+            remark: None,
+            created_at: 0,
+            updated_at: 0,
+            is_valid: true,
+            sensitive: false,
+            expandable: false,
+        },
+    };
+    variable.name = name.to_string();
+    variable.value = value.to_string();
+    projected.push(variable);
+
+    projected
+}
+
+// 写入会使环境块越过硬性上限（或让已超限的环境块更大）时拒绝
+pub fn ensure_change_within_limits(
+    before: &[EnvironmentVariable],
+    after: &[EnvironmentVariable],
+    syntax: ReferenceSyntax,
+) -> Result<(), String> {
+    let before_report = analyze(before, syntax);
+    let after_report = analyze(after, syntax);
+
+    if after_report.block_expanded_length > ENVIRONMENT_BLOCK_LIMIT
+        && after_report.block_expanded_length > before_report.block_expanded_length
+    {
+        return Err(format!(
+            "修改后环境块展开将达到 {} 个字符，超过 {} 个字符的上限",
+            after_report.block_expanded_length, ENVIRONMENT_BLOCK_LIMIT
+        ));
+    }
+
+    let oversized: Vec<&VariableSize> = after_report
+        .variables
+        .iter()
+        .filter(|size| size.expanded_length > MAX_VARIABLE_LENGTH)
+        .filter(|size| {
+            !before_report
+                .variables
+                .iter()
+                .any(|old| old.id == size.id && old.expanded_length >= size.expanded_length)
+        })
+        .collect();
+    if let Some(size) = oversized.first() {
+        return Err(format!(
+            "修改后 {} 展开将达到 {} 个字符，超过 {} 个字符的上限",
+            size.name, size.expanded_length, MAX_VARIABLE_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn rules(report: &SizeReport) -> Vec<(&str, Severity)> {
+        report.findings.iter().map(|f| (f.rule_id.as_str(), f.severity)).collect()
+    }
+
+    #[test]
+    fn builds_block_with_system_precedence_and_joined_path() {
+        let variables = vec![
+            var("user", "Path", "C:\\b"),
+            var("user", "TEMP", "C:\\user-temp"),
+            var("system", "PATH", "C:\\a;"),
+            var("system", "TEMP", "C:\\t"),
+        ];
+        let report = analyze(&variables, ReferenceSyntax::Windows);
+
+        // PATH=C:\a;C:\b 与 TEMP=C:\t，再加结尾的 NUL
+        assert_eq!(report.block_raw_length, 1 + ("PATH".len() + "C:\\a;C:\\b".len() + 2) + ("TEMP".len() + "C:\\t".len() + 2));
+        assert_eq!(report.block_limit, ENVIRONMENT_BLOCK_LIMIT);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn measures_expanded_lengths_in_characters() {
+        let variables = vec![var("user", "BASE", "目录"), var("user", "TWICE", "%BASE%%BASE%")];
+        let report = analyze(&variables, ReferenceSyntax::Windows);
+        let twice = report.variables.iter().find(|size| size.name == "TWICE").unwrap();
+
+        assert_eq!(twice.raw_length, "TWICE".len() + "%BASE%%BASE%".len() + 2);
+        assert_eq!(twice.expanded_length, "TWICE".len() + 4 + 2);
+        assert_eq!(report.block_expanded_length, 1 + (4 + 2 + 2) + (5 + 4 + 2));
+    }
+
+    #[test]
+    fn reports_setx_and_legacy_path_limits() {
+        let report = analyze(&[var("user", "LONG", &"x".repeat(SETX_LIMIT + 1))], ReferenceSyntax::Windows);
+        assert_eq!(rules(&report), vec![("limit.setx", Severity::Warning)]);
+        let report = analyze(&[var("user", "LONG", &"x".repeat(SETX_LIMIT))], ReferenceSyntax::Windows);
+        assert!(report.findings.is_empty());
+
+        let report = analyze(&[var("system", "Path", &"p".repeat(LEGACY_PATH_LIMIT + 1))], ReferenceSyntax::Windows);
+        assert!(rules(&report).contains(&("limit.path-legacy", Severity::Warning)));
+        let report = analyze(&[var("system", "Path", &"p".repeat(LEGACY_PATH_LIMIT))], ReferenceSyntax::Windows);
+        assert!(rules(&report).contains(&("limit.path-legacy", Severity::Info)));
+    }
+
+    #[test]
+    fn reports_block_over_and_near_the_limit() {
+        let half = "x".repeat(ENVIRONMENT_BLOCK_LIMIT / 2);
+        let report = analyze(&[var("user", "A", &half), var("user", "B", &half)], ReferenceSyntax::Windows);
+        assert!(report.block_expanded_length > ENVIRONMENT_BLOCK_LIMIT);
+        assert!(rules(&report).contains(&("limit.block", Severity::Error)));
+
+        let near = "x".repeat(ENVIRONMENT_BLOCK_LIMIT * 95 / 200);
+        let report = analyze(&[var("user", "A", &near), var("user", "B", &near)], ReferenceSyntax::Windows);
+        assert!(rules(&report).contains(&("limit.block", Severity::Warning)));
+    }
+
+    #[test]
+    fn projects_additions_updates_and_renames() {
+        let variables = vec![var("user", "OLD", "1"), var("system", "OLD", "2")];
+
+        let added = project_assignment(&variables, "user", None, "NEW", "3");
+        assert_eq!(added.len(), 3);
+        assert_eq!(added.last().unwrap().id, "user_NEW");
+
+        let updated = project_assignment(&variables, "user", None, "old", "4");
+        assert_eq!(updated.len(), 2);
+        let user = updated.iter().find(|v| v.var_type == "user").unwrap();
+        assert_eq!((user.name.as_str(), user.value.as_str()), ("old", "4"));
+
+        let renamed = project_assignment(&variables, "system", Some("OLD"), "RENAMED", "2");
+        let names: Vec<(&str, &str)> = renamed.iter().map(|v| (v.var_type.as_str(), v.name.as_str())).collect();
+        assert_eq!(names, vec![("user", "OLD"), ("system", "RENAMED")]);
+    }
+
+    #[test]
+    fn rejects_changes_that_grow_past_the_limit() {
+        let before = vec![var("user", "A", &"x".repeat(20_000))];
+        let after = project_assignment(&before, "user", None, "B", &"y".repeat(20_000));
+        let err = ensure_change_within_limits(&before, &after, ReferenceSyntax::Windows).unwrap_err();
+        assert!(err.contains("环境块"));
+
+        // 已经超限时，缩小环境块的修改仍然允许
+        let over = vec![var("user", "A", &"x".repeat(20_000)), var("user", "B", &"y".repeat(20_000))];
+        let shrunk = project_assignment(&over, "user", None, "B", "y");
+        assert!(ensure_change_within_limits(&over, &shrunk, ReferenceSyntax::Windows).is_ok());
+
+        let before = vec![var("user", "BIG", &"x".repeat(20_000))];
+        let after = project_assignment(&before, "user", None, "TWICE", "%BIG%%BIG%");
+        let err = ensure_change_within_limits(&before, &after, ReferenceSyntax::Windows).unwrap_err();
+        assert!(err.contains("环境块"));
+    }
+}
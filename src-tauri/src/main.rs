@@ -8,8 +8,30 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod bundle;
 mod commands;
+mod expand;
+mod export_schema;
+mod exporters;
+mod import_plan;
+mod importers;
+mod limits;
+mod metadata;
+mod profiles;
+mod query;
+mod saved_searches;
+mod schema;
+mod search;
+mod secrets;
+mod sensitive;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod toolchain;
 mod tray;
+mod validation;
+mod vault;
+mod watcher;
 
 fn main() {
     tauri::Builder::default()
@@ -20,8 +42,52 @@ fn main() {
             commands::update_environment_variable,
             commands::delete_environment_variable,
             commands::validate_environment_variable,
+            commands::validate_all,
+            commands::get_validation_rules,
+            commands::check_variable_assignment,
+            commands::check_size_limits,
+            commands::check_against_schema,
+            commands::apply_schema_fixes,
+            commands::get_expansion_diagnostics,
+            commands::get_effective_environment,
+            commands::get_dependency_graph,
+            commands::get_variable_dependents,
             commands::search_environment_variables,
+            commands::parse_search_query,
+            commands::reveal_environment_variable,
+            commands::set_variable_sensitive,
+            commands::scan_for_secrets,
+            commands::get_secret_rules,
+            commands::vault_status,
+            commands::vault_unlock,
+            commands::vault_lock,
+            commands::vault_set_secret,
+            commands::vault_delete_secret,
+            commands::vault_list,
+            commands::move_variable_to_vault,
+            commands::restore_secret_from_vault,
+            commands::run_with_secrets,
+            commands::list_profiles,
+            commands::save_profile,
+            commands::delete_profile,
+            commands::get_active_profile,
+            commands::resolve_profile,
+            commands::activate_profile,
+            commands::deactivate_profile,
+            commands::list_templates,
+            commands::save_template,
+            commands::delete_template,
+            commands::instantiate_template,
+            commands::apply_template,
+            commands::create_saved_search,
+            commands::list_saved_searches,
+            commands::delete_saved_search,
+            commands::run_saved_search,
+            commands::get_smart_filter_counts,
             commands::export_environment_variables,
+            commands::get_export_schema,
+            commands::parse_import_file,
+            commands::preview_import,
             commands::import_environment_variables,
             commands::check_for_updates,
             commands::get_settings,
@@ -31,6 +97,7 @@ fn main() {
         .setup(|app| {
             #[cfg(target_os = "windows")]
             tray::create_tray(app.handle())?;
+            watcher::start(app.handle().clone());
             Ok(())
         })
         .on_menu_event(tray::handle_menu_event)
@@ -0,0 +1,173 @@
+// Persistent per-variable metadata (timestamps, remarks and sensitivity) kept next to settings.json
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::commands::{get_settings_dir, EnvironmentVariable};
+
+// 注册表不记录单个值的创建、修改时间，这里按变量 ID 自行记录
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VariableMetadata {
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+    // 上次记录时值的指纹，用于发现应用外部的修改
+    #[serde(rename = "valueHash")]
+    pub value_hash: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+    // 手动标记的敏感状态，覆盖按名称的判断；为空时按名称判断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct MetadataStore {
+    #[serde(default)]
+    pub variables: HashMap<String, VariableMetadata>,
+}
+
+// 监视线程和各个命令都会读改写 metadata.json，同一时间只允许一个读改写过程，避免互相覆盖
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+fn metadata_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("metadata.json"))
+}
+
+// FNV-1a，结果在不同版本间保持稳定
+fn value_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl MetadataStore {
+    // 在锁内加载、修改并保存记录；f 返回 false 表示没有修改，不写回文件
+    pub fn update(f: impl FnOnce(&mut Self) -> bool) -> Result<(), String> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let path = metadata_path()?;
+        let mut store = Self::load_from(&path)?;
+        if f(&mut store) {
+            store.save_to(&path)?;
+        }
+        Ok(())
+    }
+
+    // 文件不存在时返回空记录
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read metadata file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse metadata: {}", e))
+    }
+
+    // 先写入同目录下的临时文件再替换，写到一半退出时不会留下损坏的 metadata.json
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content)
+            .map_err(|e| format!("Failed to write metadata file: {}", e))?;
+        fs::rename(&temp_path, path).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Failed to replace metadata file: {}", e)
+        })
+    }
+
+    // 将记录的时间和备注填入读取到的变量；首次出现或值在外部被修改时更新记录，返回记录是否有变化
+    pub fn apply(&mut self, variables: &mut [EnvironmentVariable], now: u64) -> bool {
+        let mut changed = false;
+        for var in variables.iter_mut() {
+            let hash = value_hash(&var.value);
+            let entry = self.variables.entry(var.id.clone()).or_insert_with(|| {
+                changed = true;
+                VariableMetadata {
+                    created_at: now,
+                    updated_at: now,
+                    value_hash: hash,
+                    remark: None,
+                    sensitive: None,
+                }
+            });
+            if entry.value_hash != hash {
+                entry.value_hash = hash;
+                entry.updated_at = now;
+                changed = true;
+            }
+
+            var.created_at = entry.created_at;
+            var.updated_at = entry.updated_at;
+            if var.remark.is_none() {
+                var.remark = entry.remark.clone();
+            }
+            if let Some(sensitive) = entry.sensitive {
+                var.sensitive = sensitive;
+            }
+        }
+        changed
+    }
+
+    // 通过应用写入变量后记录修改时间和备注
+    pub fn record_write(&mut self, id: &str, value: &str, remark: Option<String>, now: u64) {
+        let entry = self.variables.entry(id.to_string()).or_insert_with(|| VariableMetadata {
+            created_at: now,
+            ..Default::default()
+        });
+        entry.updated_at = now;
+        entry.value_hash = value_hash(value);
+        if remark.is_some() {
+            entry.remark = remark;
+        }
+    }
+
+    // 手动标记或取消敏感状态，None 表示恢复按名称判断
+    pub fn set_sensitive(&mut self, id: &str, sensitive: Option<bool>, now: u64) {
+        let entry = self.variables.entry(id.to_string()).or_insert_with(|| VariableMetadata {
+            created_at: now,
+            updated_at: now,
+            ..Default::default()
+        });
+        entry.sensitive = sensitive;
+    }
+
+    // 重命名时迁移记录
+    pub fn rename(&mut self, old_id: &str, new_id: &str) {
+        if let Some(entry) = self.variables.remove(old_id) {
+            self.variables.insert(new_id.to_string(), entry);
+        }
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.variables.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn saves_through_a_temporary_file() {
+        let path = temp_dir("metadata").join("metadata.json");
+        let mut store = MetadataStore::default();
+        store.record_write("user_JAVA_HOME", "C:\\jdk", Some("JDK 21".to_string()), 100);
+        store.save_to(&path).unwrap();
+
+        // 覆盖已有文件后不留下临时文件
+        store.set_sensitive("user_JAVA_HOME", Some(true), 200);
+        store.save_to(&path).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let loaded = MetadataStore::load_from(&path).unwrap();
+        let entry = &loaded.variables["user_JAVA_HOME"];
+        assert_eq!((entry.created_at, entry.updated_at), (100, 100));
+        assert_eq!(entry.remark.as_deref(), Some("JDK 21"));
+        assert_eq!(entry.sensitive, Some(true));
+    }
+}
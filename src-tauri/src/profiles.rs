@@ -0,0 +1,577 @@
+// Named, layered profiles of variable assignments and deletions, stored in profiles.json next to settings.json
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::{get_settings_dir, BatchChange, EnvironmentVariable};
+
+// PATH 类变量的条目操作，依次执行 remove、prepend、append
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PathOps {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prepend: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub append: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+}
+
+// 单个作用域内的修改：set 写入或覆盖，unset 删除，path 按条目修改 PATH 类变量
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScopeChanges {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub path: BTreeMap<String, PathOps>,
+}
+
+impl ScopeChanges {
+    fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.unset.is_empty() && self.path.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // 继承的配置，按顺序叠加，后面的覆盖前面的，自身的修改最后叠加
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+    #[serde(default)]
+    pub user: ScopeChanges,
+    #[serde(default)]
+    pub system: ScopeChanges,
+    #[serde(rename = "createdAt", default)]
+    pub created_at: u64,
+    #[serde(rename = "updatedAt", default)]
+    pub updated_at: u64,
+}
+
+impl Profile {
+    fn scopes(&self) -> [(&'static str, &ScopeChanges); 2] {
+        [("user", &self.user), ("system", &self.system)]
+    }
+}
+
+// 启用配置前变量的状态；value 为空表示变量原本不存在
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreviousValue {
+    pub scope: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    // 原来的注册表类型是否为 REG_EXPAND_SZ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expandable: bool,
+}
+
+// 当前启用的配置及停用时需要恢复的值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActiveProfile {
+    pub name: String,
+    #[serde(rename = "activatedAt")]
+    pub activated_at: u64,
+    pub previous: Vec<PreviousValue>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ProfileStore {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<ActiveProfile>,
+}
+
+fn store_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("profiles.json"))
+}
+
+impl ProfileStore {
+    pub fn load() -> Result<Self, String> {
+        let path = store_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read profiles: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse profiles: {}", e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+        fs::write(store_path()?, content)
+            .map_err(|e| format!("Failed to write profiles: {}", e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| format!("配置 '{}' 不存在", name))
+    }
+
+    // 按名称新增或替换配置，保留原来的创建时间
+    pub fn upsert(&mut self, mut profile: Profile, now: u64) -> Result<Profile, String> {
+        profile.name = profile.name.trim().to_string();
+        if profile.name.is_empty() {
+            return Err("配置名称不能为空".to_string());
+        }
+        if profile.user.is_empty() && profile.system.is_empty() && profile.parents.is_empty() {
+            return Err("配置中没有任何修改".to_string());
+        }
+        for (scope, changes) in profile.scopes() {
+            if let Some(name) = changes.unset.iter().find(|name| changes.set.keys().any(|set| set.eq_ignore_ascii_case(name))) {
+                return Err(format!("{} 变量 {} 不能同时写入和删除", scope, name));
+            }
+            let whole: Vec<&String> = changes.set.keys().chain(&changes.unset).collect();
+            if let Some(name) = changes.path.keys().find(|name| whole.iter().any(|other| other.eq_ignore_ascii_case(name))) {
+                return Err(format!("{} 变量 {} 不能同时整体修改和按条目修改", scope, name));
+            }
+        }
+        // 父配置必须存在，且不能直接或间接继承自身
+        for parent in &profile.parents {
+            if parent == &profile.name || self.chain(parent)?.contains(&profile.name) {
+                return Err(format!("配置 '{}' 继承 '{}' 会形成循环", profile.name, parent));
+            }
+        }
+        if self.active.as_ref().is_some_and(|active| active.name == profile.name) {
+            return Err(format!("配置 '{}' 正在使用，请先停用再修改", profile.name));
+        }
+
+        profile.updated_at = now;
+        match self.profiles.iter_mut().find(|existing| existing.name == profile.name) {
+            Some(existing) => {
+                profile.created_at = existing.created_at;
+                *existing = profile.clone();
+            }
+            None => {
+                profile.created_at = now;
+                self.profiles.push(profile.clone());
+            }
+        }
+        Ok(profile)
+    }
+
+    // 叠加顺序：先按声明顺序展开各父配置（深度优先，共同的祖先只叠加一次），最后是配置本身
+    pub fn chain(&self, name: &str) -> Result<Vec<String>, String> {
+        let mut chain = Vec::new();
+        self.visit(name, &mut Vec::new(), &mut chain)?;
+        Ok(chain)
+    }
+
+    fn visit(&self, name: &str, path: &mut Vec<String>, chain: &mut Vec<String>) -> Result<(), String> {
+        if path.iter().any(|visiting| visiting == name) {
+            path.push(name.to_string());
+            return Err(format!("配置继承存在循环: {}", path.join(" -> ")));
+        }
+        if chain.iter().any(|done| done == name) {
+            return Ok(());
+        }
+        let profile = self.get(name)?;
+        path.push(name.to_string());
+        for parent in &profile.parents {
+            self.visit(parent, path, chain)?;
+        }
+        path.pop();
+        chain.push(name.to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        if self.active.as_ref().is_some_and(|active| active.name == name) {
+            return Err(format!("配置 '{}' 正在使用，请先停用再删除", name));
+        }
+        if let Some(child) = self.profiles.iter().find(|profile| profile.parents.iter().any(|parent| parent == name)) {
+            return Err(format!("配置 '{}' 被 '{}' 继承，不能删除", name, child.name));
+        }
+        let before = self.profiles.len();
+        self.profiles.retain(|profile| profile.name != name);
+        if self.profiles.len() == before {
+            return Err(format!("配置 '{}' 不存在", name));
+        }
+        Ok(())
+    }
+}
+
+fn find<'a>(current: &'a [EnvironmentVariable], scope: &str, name: &str) -> Option<&'a EnvironmentVariable> {
+    current.iter().find(|var| var.var_type == scope && var.name.eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    // 按条目修改时作为基础的注册表当前值
+    Current,
+    Set,
+    Unset,
+    Prepend,
+    Append,
+    Remove,
+}
+
+// 最终结果中某个值或条目的来源
+#[derive(Debug, Serialize, Clone)]
+pub struct Provenance {
+    // 来自注册表当前值时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub operation: Operation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+}
+
+// 解析后的单个变量；value 为空表示删除，provenance 按叠加顺序排列，被覆盖的来源不再列出
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedVariable {
+    pub scope: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub provenance: Vec<Provenance>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedProfile {
+    pub name: String,
+    // 叠加顺序，后面的优先
+    pub chain: Vec<String>,
+    pub variables: Vec<ResolvedVariable>,
+}
+
+// 叠加过程中变量的状态：整体设置或删除的基础值，加上之后的条目操作
+enum Base {
+    Current,
+    Set(String),
+    Unset,
+}
+
+struct Layered {
+    scope: String,
+    name: String,
+    base: Base,
+    edits: Vec<PathOps>,
+    provenance: Vec<Provenance>,
+}
+
+// 比较条目时忽略大小写和结尾的斜杠
+fn entry_key(entry: &str) -> String {
+    entry.trim().trim_end_matches(['\\', '/']).to_lowercase()
+}
+
+// 按 remove、prepend、append 的顺序修改分号分隔的条目；已存在的条目被移动到新位置
+fn apply_path_ops(value: &str, ops: &PathOps) -> String {
+    let mut entries: Vec<String> = value
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    let moved: HashSet<String> = ops
+        .remove
+        .iter()
+        .chain(&ops.prepend)
+        .chain(&ops.append)
+        .map(|entry| entry_key(entry))
+        .collect();
+    entries.retain(|entry| !moved.contains(&entry_key(entry)));
+
+    let mut result: Vec<String> = Vec::new();
+    for entry in ops.prepend.iter().chain(&entries).chain(&ops.append) {
+        if !result.iter().any(|existing| entry_key(existing) == entry_key(entry)) {
+            result.push(entry.clone());
+        }
+    }
+    result.join(";")
+}
+
+type LayerKey = (String, String);
+
+// 取出变量的叠加状态，第一次出现时创建；名称按最后一次出现的写法
+fn layer<'a>(layered: &'a mut HashMap<LayerKey, Layered>, scope: &str, name: &str) -> &'a mut Layered {
+    let var = layered
+        .entry((scope.to_string(), name.to_uppercase())).or_insert_with(|| Layered {
+        scope: scope.to_string(),
+        name: name.to_string(),
+        base: Base::Current,
+        edits: Vec::new(),
+        provenance: Vec::new(),
+    });
+    var.name = name.to_string();
+    var
+}
+
+// 按继承链叠加配置，得到每个变量的最终值及来源；按条目修改的变量以注册表当前值为基础
+pub fn resolve(store: &ProfileStore, name: &str, current: &[EnvironmentVariable]) -> Result<ResolvedProfile, String> {
+    let chain = store.chain(name)?;
+    let mut layered: HashMap<LayerKey, Layered> = HashMap::new();
+
+    for profile_name in &chain {
+        let profile = store.get(profile_name)?;
+        let source = |operation: Operation, entry: Option<&String>| Provenance {
+            profile: Some(profile_name.clone()),
+            operation,
+            entry: entry.cloned(),
+        };
+        for (scope, changes) in profile.scopes() {
+            for (var_name, value) in &changes.set {
+                let var = layer(&mut layered, scope, var_name);
+                var.base = Base::Set(value.clone());
+                var.edits.clear();
+                var.provenance = vec![source(Operation::Set, None)];
+            }
+            for var_name in &changes.unset {
+                let var = layer(&mut layered, scope, var_name);
+                var.base = Base::Unset;
+                var.edits.clear();
+                var.provenance = vec![source(Operation::Unset, None)];
+            }
+            for (var_name, ops) in &changes.path {
+                let var = layer(&mut layered, scope, var_name);
+                var.edits.push(ops.clone());
+                var.provenance.extend(ops.remove.iter().map(|entry| source(Operation::Remove, Some(entry))));
+                var.provenance.extend(ops.prepend.iter().map(|entry| source(Operation::Prepend, Some(entry))));
+                var.provenance.extend(ops.append.iter().map(|entry| source(Operation::Append, Some(entry))));
+            }
+        }
+    }
+
+    let mut variables: Vec<ResolvedVariable> = layered
+        .into_values()
+        .map(|mut var| {
+            let mut value = match &var.base {
+                Base::Set(value) => Some(value.clone()),
+                Base::Unset => None,
+                Base::Current => {
+                    let existing = find(current, &var.scope, &var.name);
+                    if let Some(existing) = existing {
+                        var.provenance.insert(0, Provenance { profile: None, operation: Operation::Current, entry: None });
+                        var.name = existing.name.clone();
+                    }
+                    existing.map(|existing| existing.value.clone())
+                }
+            };
+            if !var.edits.is_empty() {
+                let mut edited = value.unwrap_or_default();
+                for ops in &var.edits {
+                    edited = apply_path_ops(&edited, ops);
+                }
+                value = Some(edited);
+            }
+            ResolvedVariable {
+                scope: var.scope,
+                name: var.name,
+                value,
+                provenance: var.provenance,
+            }
+        })
+        .collect();
+    variables.sort_by(|a, b| a.scope.cmp(&b.scope).then_with(|| a.name.to_uppercase().cmp(&b.name.to_uppercase())));
+
+    Ok(ResolvedProfile {
+        name: name.to_string(),
+        chain,
+        variables,
+    })
+}
+
+// 计算启用配置需要的修改，以及每个修改前的状态；值已相同或要删除的变量不存在时跳过
+pub fn plan_activation(
+    resolved: &ResolvedProfile,
+    current: &[EnvironmentVariable],
+    expandable: &HashSet<String>,
+) -> Vec<(BatchChange, PreviousValue)> {
+    let mut planned = Vec::new();
+    for var in &resolved.variables {
+        let existing = find(current, &var.scope, &var.name);
+        let was_expandable = existing.is_some_and(|existing| expandable.contains(&existing.id));
+        let previous = PreviousValue {
+            scope: var.scope.clone(),
+            name: existing.map(|existing| existing.name.clone()).unwrap_or_else(|| var.name.clone()),
+            value: existing.map(|existing| existing.value.clone()),
+            expandable: was_expandable,
+        };
+        let change = match (&var.value, existing) {
+            (Some(value), Some(existing)) if &existing.value == value => continue,
+            // 覆盖已有变量时保留其注册表类型
+            (Some(value), _) => BatchChange::Set {
+                var_type: var.scope.clone(),
+                name: var.name.clone(),
+                value: value.clone(),
+                expandable: was_expandable,
+            },
+            (None, Some(existing)) => BatchChange::Delete {
+                var_type: var.scope.clone(),
+                name: existing.name.clone(),
+            },
+            (None, None) => continue,
+        };
+        planned.push((change, previous));
+    }
+    planned
+}
+// 停用配置时恢复原值的修改，按启用时的相反顺序执行
+pub fn plan_restore(previous: &[PreviousValue]) -> Vec<BatchChange> {
+    previous
+        .iter()
+        .rev()
+        .map(|prev| match &prev.value {
+            Some(value) => BatchChange::Set {
+                var_type: prev.scope.clone(),
+                name: prev.name.clone(),
+                value: value.clone(),
+                expandable: prev.expandable,
+            },
+            None => BatchChange::Delete {
+                var_type: prev.scope.clone(),
+                name: prev.name.clone(),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+
+    fn profile(name: &str, parents: &[&str], user: ScopeChanges) -> Profile {
+        Profile {
+            name: name.to_string(),
+            description: None,
+            parents: parents.iter().map(|parent| parent.to_string()).collect(),
+            user,
+            system: ScopeChanges::default(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn set(pairs: &[(&str, &str)]) -> ScopeChanges {
+        ScopeChanges {
+            set: pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            ..ScopeChanges::default()
+        }
+    }
+
+    fn path(name: &str, ops: PathOps) -> ScopeChanges {
+        ScopeChanges {
+            path: BTreeMap::from([(name.to_string(), ops)]),
+            ..ScopeChanges::default()
+        }
+    }
+
+    fn entries(list: &[&str]) -> Vec<String> {
+        list.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn applies_path_ops_in_order_and_moves_existing_entries() {
+        let ops = PathOps {
+            prepend: entries(&[r"C:\jdk\bin"]),
+            append: entries(&[r"c:\tools\"]),
+            remove: entries(&[r"C:\old"]),
+        };
+        assert_eq!(
+            apply_path_ops(r"C:\Tools;C:\old\;;C:\Windows;C:\JDK\bin", &ops),
+            r"C:\jdk\bin;C:\Windows;c:\tools\"
+        );
+        assert_eq!(apply_path_ops("", &ops), r"C:\jdk\bin;c:\tools\");
+    }
+
+    #[test]
+    fn resolves_layers_with_provenance() {
+        let mut store = ProfileStore::default();
+        store.upsert(profile("base", &[], set(&[("JAVA_HOME", r"C:\jdk8"), ("MODE", "base")])), 1).unwrap();
+        store
+            .upsert(
+                profile("java17", &["base"], ScopeChanges {
+                    set: BTreeMap::from([("java_home".to_string(), r"C:\jdk17".to_string())]),
+                    unset: entries(&["OLD"]),
+                    path: BTreeMap::from([("Path".to_string(), PathOps { prepend: entries(&[r"C:\jdk17\bin"]), ..PathOps::default() })]),
+                }),
+                2,
+            )
+            .unwrap();
+
+        let current = vec![var("user", "Path", r"C:\Windows"), var("user", "OLD", "x")];
+        let resolved = resolve(&store, "java17", &current).unwrap();
+        assert_eq!(resolved.chain, vec!["base", "java17"]);
+
+        let values: Vec<(&str, Option<&str>)> = resolved.variables.iter().map(|v| (v.name.as_str(), v.value.as_deref())).collect();
+        assert_eq!(
+            values,
+            vec![
+                ("java_home", Some(r"C:\jdk17")),
+                ("MODE", Some("base")),
+                ("OLD", None),
+                ("Path", Some(r"C:\jdk17\bin;C:\Windows")),
+            ]
+        );
+
+        let path = &resolved.variables[3];
+        let operations: Vec<Operation> = path.provenance.iter().map(|p| p.operation).collect();
+        assert_eq!(operations, vec![Operation::Current, Operation::Prepend]);
+        assert_eq!(resolved.variables[0].provenance[0].profile.as_deref(), Some("java17"));
+        assert_eq!(resolved.variables[1].provenance[0].profile.as_deref(), Some("base"));
+    }
+
+    #[test]
+    fn path_ops_after_a_set_build_on_the_set_value() {
+        let mut store = ProfileStore::default();
+        store.upsert(profile("a", &[], set(&[("PATH", r"C:\a")])), 1).unwrap();
+        store.upsert(profile("b", &["a"], path("path", PathOps { append: entries(&[r"C:\b"]), ..PathOps::default() })), 1).unwrap();
+
+        let resolved = resolve(&store, "b", &[var("user", "PATH", r"C:\ignored")]).unwrap();
+        assert_eq!(resolved.variables[0].value.as_deref(), Some(r"C:\a;C:\b"));
+    }
+
+    #[test]
+    fn rejects_inheritance_cycles_and_conflicting_changes() {
+        let mut store = ProfileStore::default();
+        store.upsert(profile("a", &[], set(&[("X", "1")])), 1).unwrap();
+        store.upsert(profile("b", &["a"], set(&[("Y", "1")])), 1).unwrap();
+        assert!(store.upsert(profile("a", &["b"], set(&[("X", "1")])), 2).is_err());
+        assert!(store.upsert(profile("c", &["missing"], set(&[("X", "1")])), 2).is_err());
+
+        // 直接写入文件形成的循环在解析时报告
+        store.profiles[0].parents = entries(&["b"]);
+        assert!(resolve(&store, "b", &[]).unwrap_err().contains("b -> a -> b"));
+
+        let mut conflicting = set(&[("X", "1")]);
+        conflicting.unset = entries(&["x"]);
+        assert!(ProfileStore::default().upsert(profile("d", &[], conflicting), 1).is_err());
+    }
+
+    #[test]
+    fn plans_activation_and_restore() {
+        let mut store = ProfileStore::default();
+        store
+            .upsert(profile("p", &[], ScopeChanges { unset: entries(&["GONE", "MISSING"]), ..set(&[("SAME", "1"), ("NEW", "2"), ("PATH", "x")]) }), 1)
+            .unwrap();
+        let current = vec![var("user", "SAME", "1"), var("user", "GONE", "old"), var("user", "Path", "%A%")];
+        let expandable = HashSet::from(["user_Path".to_string()]);
+
+        let resolved = resolve(&store, "p", &current).unwrap();
+        let planned = plan_activation(&resolved, &current, &expandable);
+        let names: Vec<&str> = planned
+            .iter()
+            .map(|(change, _)| match change {
+                BatchChange::Set { name, .. } | BatchChange::Delete { name, .. } => name.as_str(),
+            })
+            .collect();
+        assert_eq!(names, vec!["GONE", "NEW", "PATH"]);
+        assert!(matches!(&planned[2].0, BatchChange::Set { expandable: true, .. }));
+
+        let previous: Vec<PreviousValue> = planned.into_iter().map(|(_, previous)| previous).collect();
+        let restore = plan_restore(&previous);
+        assert!(matches!(&restore[0], BatchChange::Set { name, value, expandable: true, .. } if name == "Path" && value == "%A%"));
+        assert!(matches!(&restore[1], BatchChange::Delete { name, .. } if name == "NEW"));
+        assert!(matches!(&restore[2], BatchChange::Set { name, value, .. } if name == "GONE" && value == "old"));
+    }
+}
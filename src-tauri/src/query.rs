@@ -0,0 +1,278 @@
+// Parser for the search query language, e.g.
+// name:JAVA* value:/C:\\Program Files\\.*/ scope:system invalid:true refs:JAVA_HOME
+use std::fmt;
+
+use crate::commands::SearchQuery;
+use crate::search::{self, FieldFilter, FilterField, MatchKind};
+
+const SCOPES: [&str; 2] = ["user", "system"];
+
+// 可用的限定符；其他 word: 前缀（如 C:\tools 中的盘符）按普通搜索词处理
+const QUALIFIERS: [&str; 9] = ["name", "value", "remark", "scope", "type", "valid", "invalid", "refs", "references"];
+
+// 解析错误，position 为出错位置的字符下标（从 0 开始）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "查询语法错误（第 {} 个字符）: {}", self.position + 1, self.message)
+    }
+}
+
+fn error<T>(position: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { position, message })
+}
+
+// 读取限定符后的值："..." 为字面量，/.../ 为正则，含 * 或 ? 的为通配符，其余为子串
+fn read_value(chars: &[char], pos: &mut usize) -> Result<(String, MatchKind), ParseError> {
+    let start = *pos;
+    let Some(&first) = chars.get(start) else {
+        return error(start, "缺少值".to_string());
+    };
+    if first.is_whitespace() {
+        return error(start, "缺少值".to_string());
+    }
+
+    if first == '"' || first == '/' {
+        let mut value = String::new();
+        let mut i = start + 1;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == first {
+                *pos = i + 1;
+                return if first == '"' {
+                    Ok((value, MatchKind::Substring))
+                } else if value.is_empty() {
+                    error(start, "正则表达式不能为空".to_string())
+                } else {
+                    Ok((value, MatchKind::Regex))
+                };
+            }
+            if c == '\\' && i + 1 < chars.len() {
+                let next = chars[i + 1];
+                if next == first || (first == '"' && next == '\\') {
+                    // \" 或 \/ 转义结束符；字符串中的 \\ 表示反斜杠
+                    value.push(next);
+                } else {
+                    // 其余转义原样保留给正则表达式
+                    value.push(c);
+                    value.push(next);
+                }
+                i += 2;
+                continue;
+            }
+            value.push(c);
+            i += 1;
+        }
+        let what = if first == '"' { "引号" } else { "正则表达式" };
+        return error(start, format!("{}没有结束", what));
+    }
+
+    let mut value = String::new();
+    while *pos < chars.len() && !chars[*pos].is_whitespace() {
+        value.push(chars[*pos]);
+        *pos += 1;
+    }
+    let kind = if value.contains('*') || value.contains('?') {
+        MatchKind::Glob
+    } else {
+        MatchKind::Substring
+    };
+    Ok((value, kind))
+}
+
+fn parse_bool(value: &str, position: usize) -> Result<bool, ParseError> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => error(position, format!("'{}' 不是布尔值，应为 true 或 false", value)),
+    }
+}
+
+// 将查询字符串解析为 SearchQuery
+pub fn parse(input: &str) -> Result<SearchQuery, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut query = SearchQuery::default();
+    let mut included_scopes: Vec<String> = Vec::new();
+    let mut excluded_scopes: Vec<String> = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        while pos < chars.len() && chars[pos].is_whitespace() {
+            pos += 1;
+        }
+        if pos >= chars.len() {
+            break;
+        }
+
+        let start = pos;
+        let negate = chars[pos] == '-' && chars.get(pos + 1).map(|c| !c.is_whitespace()).unwrap_or(false);
+        if negate {
+            pos += 1;
+        }
+
+        let mut end = pos;
+        while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+            end += 1;
+        }
+        let qualifier = if end > pos && chars.get(end) == Some(&':') {
+            let qualifier: String = chars[pos..end].iter().collect::<String>().to_lowercase();
+            QUALIFIERS.contains(&qualifier.as_str()).then_some(qualifier)
+        } else {
+            None
+        };
+        if qualifier.is_some() {
+            pos = end + 1;
+        }
+
+        let value_start = pos;
+        let (value, kind) = read_value(&chars, &mut pos)?;
+
+        let field = match qualifier.as_deref() {
+            None => Some(FilterField::Any),
+            Some("name") => Some(FilterField::Name),
+            Some("value") => Some(FilterField::Value),
+            Some("remark") => Some(FilterField::Remark),
+            _ => None,
+        };
+        if let Some(field) = field {
+            let filter = FieldFilter {
+                field,
+                pattern: value,
+                kind,
+                negate,
+            };
+            if let Err(message) = search::compile_filter(&filter) {
+                return error(value_start, message);
+            }
+            query.filters.push(filter);
+            continue;
+        }
+
+        match qualifier.as_deref().unwrap_or_default() {
+            "scope" | "type" => {
+                let scope = value.to_lowercase();
+                if !SCOPES.contains(&scope.as_str()) {
+                    return error(value_start, format!("未知的作用域 '{}'，应为 user 或 system", value));
+                }
+                if negate {
+                    excluded_scopes.push(scope);
+                } else {
+                    included_scopes.push(scope);
+                }
+            }
+            qualifier @ ("valid" | "invalid") => {
+                let mut valid = parse_bool(&value, value_start)?;
+                if qualifier == "invalid" {
+                    valid = !valid;
+                }
+                if negate {
+                    valid = !valid;
+                }
+                if query.is_valid.is_some_and(|existing| existing != valid) {
+                    return error(start, "有效性条件相互矛盾".to_string());
+                }
+                query.is_valid = Some(valid);
+            }
+            "refs" | "references" => {
+                if negate {
+                    return error(start, "refs 条件不支持取反".to_string());
+                }
+                query.references.get_or_insert_with(Vec::new).push(value.to_uppercase());
+            }
+            // 不在 QUALIFIERS 中的前缀已作为普通搜索词处理
+            other => unreachable!("未处理的限定符 '{}'", other),
+        }
+    }
+
+    if !included_scopes.is_empty() || !excluded_scopes.is_empty() {
+        let base: Vec<String> = if included_scopes.is_empty() {
+            SCOPES.iter().map(|s| s.to_string()).collect()
+        } else {
+            included_scopes
+        };
+        query.types = Some(base.into_iter().filter(|s| !excluded_scopes.contains(s)).collect());
+    }
+
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_qualified_filters() {
+        let query = parse(r#"name:JAVA* value:/C:\\Program Files\\.*/ remark:"my note" -value:tmp"#).unwrap();
+        let filters: Vec<_> = query.filters.iter().map(|f| (f.field, f.pattern.as_str(), f.kind, f.negate)).collect();
+        assert_eq!(
+            filters,
+            vec![
+                (FilterField::Name, "JAVA*", MatchKind::Glob, false),
+                (FilterField::Value, r"C:\\Program Files\\.*", MatchKind::Regex, false),
+                (FilterField::Remark, "my note", MatchKind::Substring, false),
+                (FilterField::Value, "tmp", MatchKind::Substring, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn bare_words_match_any_field() {
+        let query = parse("  nodejs  ").unwrap();
+        assert_eq!(query.filters.len(), 1);
+        assert_eq!(query.filters[0].field, FilterField::Any);
+        assert_eq!(query.filters[0].pattern, "nodejs");
+    }
+
+    #[test]
+    fn unknown_prefixes_are_bare_terms() {
+        for input in [r"C:\old-tools", "d:/tools", "color:red"] {
+            let query = parse(input).unwrap();
+            let filters: Vec<_> = query.filters.iter().map(|f| (f.field, f.pattern.as_str(), f.kind)).collect();
+            assert_eq!(filters, vec![(FilterField::Any, input, MatchKind::Substring)], "{}", input);
+        }
+
+        let query = parse(r"-C:\old-tools Name:java").unwrap();
+        assert_eq!(query.filters[0].field, FilterField::Any);
+        assert!(query.filters[0].negate);
+        assert_eq!(query.filters[1].field, FilterField::Name);
+    }
+
+    #[test]
+    fn quoted_values_unescape_quotes_and_backslashes() {
+        let query = parse(r#"value:"say \"hi\" C:\\x""#).unwrap();
+        assert_eq!(query.filters[0].pattern, r#"say "hi" C:\x"#);
+        assert_eq!(query.filters[0].kind, MatchKind::Substring);
+    }
+
+    #[test]
+    fn parses_scope_validity_and_refs() {
+        let query = parse("scope:system invalid:true refs:java_home").unwrap();
+        assert_eq!(query.types, Some(vec!["system".to_string()]));
+        assert_eq!(query.is_valid, Some(false));
+        assert_eq!(query.references, Some(vec!["JAVA_HOME".to_string()]));
+
+        let query = parse("-scope:user").unwrap();
+        assert_eq!(query.types, Some(vec!["system".to_string()]));
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let err = parse("name:foo scope:machine").unwrap_err();
+        assert_eq!(err.position, 15);
+
+        let err = parse(r#"value:"open"#).unwrap_err();
+        assert_eq!(err.position, 6);
+
+        let err = parse("value:/(/").unwrap_err();
+        assert_eq!(err.position, 6);
+
+        assert!(parse("name:").is_err());
+        assert!(parse("valid:true invalid:true").is_err());
+        assert!(parse("-refs:PATH").is_err());
+    }
+}
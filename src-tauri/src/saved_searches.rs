@@ -0,0 +1,138 @@
+// Saved searches and smart filters, stored in saved-searches.json next to settings.json
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::commands::{get_settings_dir, SearchQuery};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub query: SearchQuery,
+    // 智能筛选：环境变化时重新计算命中数量
+    #[serde(rename = "smartFilter", default)]
+    pub smart_filter: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+}
+
+// 智能筛选的实时命中数量
+#[derive(Debug, Serialize, Clone)]
+pub struct SmartFilterCount {
+    pub id: String,
+    pub name: String,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SavedSearchStore {
+    #[serde(default)]
+    pub searches: Vec<SavedSearch>,
+}
+
+fn store_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("saved-searches.json"))
+}
+
+impl SavedSearchStore {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(&store_path()?)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&store_path()?)
+    }
+
+    // 文件不存在时返回空列表
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read saved searches: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse saved searches: {}", e))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize saved searches: {}", e))?;
+        fs::write(path, content)
+            .map_err(|e| format!("Failed to write saved searches: {}", e))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SavedSearch> {
+        self.searches.iter().find(|search| search.id == id)
+    }
+
+    // 名称不能为空且不能重复（忽略大小写）
+    pub fn add(&mut self, search: SavedSearch) -> Result<(), String> {
+        if search.name.trim().is_empty() {
+            return Err("搜索名称不能为空".to_string());
+        }
+        if self.searches.iter().any(|existing| existing.name.eq_ignore_ascii_case(&search.name)) {
+            return Err(format!("已存在名为 '{}' 的搜索", search.name));
+        }
+        self.searches.push(search);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        let before = self.searches.len();
+        self.searches.retain(|search| search.id != id);
+        if self.searches.len() == before {
+            return Err(format!("保存的搜索 '{}' 不存在", id));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn saved(id: &str, name: &str) -> SavedSearch {
+        SavedSearch {
+            id: id.to_string(),
+            name: name.to_string(),
+            query: serde_json::from_value(serde_json::json!({ "nameKeyword": "java", "splitPathEntries": true })).unwrap(),
+            smart_filter: true,
+            created_at: 1,
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_duplicate_names() {
+        let mut store = SavedSearchStore::default();
+        store.add(saved("1", "Java")).unwrap();
+        assert_eq!(store.add(saved("2", " ")).unwrap_err(), "搜索名称不能为空");
+        assert_eq!(store.add(saved("3", "JAVA")).unwrap_err(), "已存在名为 'JAVA' 的搜索");
+
+        assert!(store.remove("missing").is_err());
+        store.remove("1").unwrap();
+        assert!(store.get("1").is_none());
+    }
+
+    #[test]
+    fn persists_searches_across_loads() {
+        let path = temp_dir("saved-searches").join("saved-searches.json");
+        assert!(SavedSearchStore::load_from(&path).unwrap().searches.is_empty());
+
+        let mut store = SavedSearchStore::default();
+        store.add(saved("1", "Java")).unwrap();
+        store.save_to(&path).unwrap();
+
+        let loaded = SavedSearchStore::load_from(&path).unwrap();
+        let search = loaded.get("1").unwrap();
+        assert_eq!(search.name, "Java");
+        assert!(search.smart_filter);
+        assert_eq!(search.query.name_keyword.as_deref(), Some("java"));
+        assert!(search.query.split_path_entries);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(SavedSearchStore::load_from(&path).is_err());
+    }
+}
@@ -0,0 +1,476 @@
+// Team schema (env-schema.toml) describing required variables and value constraints
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::commands::EnvironmentVariable;
+use crate::expand::{Expander, ReferenceSyntax};
+use crate::sensitive;
+use crate::validation::Severity;
+
+// env-schema.toml 的结构：
+//
+// [variables.JAVA_HOME]
+// required = true
+// scope = "user"          # user、system 或 any
+// type = "path"           # string、path、url、integer、bool、enum
+// must_exist = true
+// pattern = "^C:\\\\.*"
+// values = ["a", "b"]     # type = "enum" 时的可选值
+// default = "C:\\jdk"     # 修复计划使用的值
+#[derive(Debug, Deserialize, Clone)]
+pub struct EnvSchema {
+    #[serde(default)]
+    pub variables: BTreeMap<String, VariableSchema>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct VariableSchema {
+    #[serde(default)]
+    pub required: bool,
+    pub scope: Option<String>,
+    #[serde(rename = "type")]
+    pub value_type: Option<String>,
+    pub pattern: Option<String>,
+    pub values: Option<Vec<String>>,
+    #[serde(default)]
+    pub must_exist: bool,
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+// 单条规则的检查结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SchemaRuleResult {
+    pub variable: String,
+    #[serde(rename = "ruleId")]
+    pub rule_id: String,
+    pub passed: bool,
+    pub severity: Severity,
+    pub message: String,
+}
+
+// 修复计划中的一项，action 为 "add" 或 "update"，分别对应 add/update 命令
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlannedFix {
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub variable: EnvironmentVariable,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SchemaReport {
+    pub results: Vec<SchemaRuleResult>,
+    pub plan: Vec<PlannedFix>,
+    // 无法自动修复（schema 中没有 default）的变量
+    pub unfixable: Vec<String>,
+}
+
+pub fn load_schema(path: &Path) -> Result<EnvSchema, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read schema file: {}", e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse schema: {}", e))
+}
+
+fn scope_matches(scope: &Option<String>, var_type: &str) -> bool {
+    match scope.as_deref() {
+        None | Some("any") => true,
+        Some(scope) => scope == var_type,
+    }
+}
+
+// 失败原因中显示的值，敏感变量的值用掩码代替
+pub fn shown_value(value: &str, sensitive: bool) -> String {
+    if sensitive {
+        sensitive::MASK.to_string()
+    } else {
+        format!("'{}'", value)
+    }
+}
+
+// Windows 绝对路径：盘符开头（C:\）或 UNC 路径（\\server\share），也可以以变量引用开头（%JAVA_HOME%\bin）
+fn check_path(value: &str) -> Result<(), &'static str> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("路径为空");
+    }
+    let bytes = value.as_bytes();
+    let has_drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    let is_unc = value.starts_with("\\\\") || value.starts_with("//");
+    let is_reference = value.starts_with('%') || value.starts_with('$');
+    if !has_drive && !is_unc && !is_reference {
+        return Err("不是绝对路径");
+    }
+    let rest = if has_drive { &value[2..] } else { value };
+    if rest.chars().any(|c| "<>:\"|?*".contains(c) || c.is_control()) {
+        return Err("包含路径中不允许的字符");
+    }
+    Ok(())
+}
+
+// 检查值是否符合声明的类型，返回失败原因；sensitive 为 true 时失败原因中不包含值
+pub fn check_type(value_type: &str, rule: &VariableSchema, value: &str, sensitive: bool) -> Result<(), String> {
+    let shown = shown_value(value, sensitive);
+    match value_type {
+        "string" => Ok(()),
+        "path" => check_path(value).map_err(|reason| format!("{} 不是有效的路径: {}", shown, reason)),
+        "url" => {
+            let re = Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*://\S+$").unwrap();
+            if re.is_match(value) {
+                Ok(())
+            } else {
+                Err(format!("{} 不是有效的 URL", shown))
+            }
+        }
+        "integer" => value
+            .trim()
+            .parse::<i64>()
+            .map(|_| ())
+            .map_err(|_| format!("{} 不是整数", shown)),
+        "bool" => match value.trim().to_lowercase().as_str() {
+            "true" | "false" | "1" | "0" | "yes" | "no" | "on" | "off" => Ok(()),
+            _ => Err(format!("{} 不是布尔值", shown)),
+        },
+        "enum" => {
+            let values = rule.values.clone().unwrap_or_default();
+            if values.iter().any(|v| v == value) {
+                Ok(())
+            } else {
+                Err(format!("{} 不在可选值 [{}] 中", shown, values.join(", ")))
+            }
+        }
+        other => Err(format!("未知的类型 '{}'", other)),
+    }
+}
+
+fn result(variable: &str, rule_id: &str, outcome: Result<String, String>) -> SchemaRuleResult {
+    let (passed, severity, message) = match outcome {
+        Ok(message) => (true, Severity::Info, message),
+        Err(message) => (false, Severity::Error, message),
+    };
+    SchemaRuleResult {
+        variable: variable.to_string(),
+        rule_id: rule_id.to_string(),
+        passed,
+        severity,
+        message,
+    }
+}
+
+// 按 schema 检查当前变量，并为缺失或无效的变量生成修复计划
+pub fn check(
+    schema: &EnvSchema,
+    variables: &[EnvironmentVariable],
+    env_map: &HashMap<String, String>,
+    syntax: ReferenceSyntax,
+) -> SchemaReport {
+    let mut expander = Expander::new(env_map, syntax);
+    let mut results = Vec::new();
+    let mut plan = Vec::new();
+    let mut unfixable = Vec::new();
+
+    for (name, rule) in &schema.variables {
+        let matching: Vec<&EnvironmentVariable> = variables
+            .iter()
+            .filter(|var| var.name.eq_ignore_ascii_case(name))
+            .collect();
+        let live = matching.iter().find(|var| scope_matches(&rule.scope, &var.var_type));
+        let target_scope = match rule.scope.as_deref() {
+            Some("system") => "system",
+            _ => "user",
+        };
+
+        let Some(var) = live else {
+            if matching.is_empty() {
+                if rule.required {
+                    results.push(result(name, "required", Err(format!("缺少必需的变量 {}", name))));
+                } else {
+                    results.push(result(name, "required", Ok(format!("可选变量 {} 未设置", name))));
+                    continue;
+                }
+            } else {
+                results.push(result(
+                    name,
+                    "scope",
+                    Err(format!("{} 应位于 {} 作用域", name, rule.scope.clone().unwrap_or_default())),
+                ));
+            }
+
+            match &rule.default {
+                Some(default) => plan.push(PlannedFix {
+                    action: "add".to_string(),
+                    id: None,
+                    variable: new_variable(target_scope, name, default, rule),
+                    reason: format!("按 schema 添加 {}", name),
+                }),
+                None => unfixable.push(name.clone()),
+            }
+            continue;
+        };
+
+        results.push(result(name, "required", Ok(format!("{} 已设置", name))));
+        let mut failures = Vec::new();
+
+        if let Some(value_type) = &rule.value_type {
+            let outcome = check_type(value_type, rule, &var.value, var.sensitive)
+                .map(|_| format!("{} 的值符合类型 {}", name, value_type));
+            if let Err(message) = &outcome {
+                failures.push(message.clone());
+            }
+            results.push(result(name, "type", outcome));
+        }
+
+        if let Some(pattern) = &rule.pattern {
+            let outcome = match Regex::new(pattern) {
+                Ok(re) if re.is_match(&var.value) => Ok(format!("{} 的值匹配 {}", name, pattern)),
+                Ok(_) => Err(format!("{} 的值不匹配 {}", name, pattern)),
+                Err(e) => Err(format!("schema 中的正则表达式无效: {}", e)),
+            };
+            if let Err(message) = &outcome {
+                failures.push(message.clone());
+            }
+            results.push(result(name, "pattern", outcome));
+        }
+
+        if rule.must_exist {
+            let expanded = expander.expand_as(&var.name, &var.value).expanded;
+            let shown = if var.sensitive { sensitive::MASK.to_string() } else { expanded.clone() };
+            let outcome = if Path::new(&expanded).exists() {
+                Ok(format!("路径存在: {}", shown))
+            } else {
+                Err(format!("路径不存在: {}", shown))
+            };
+            if let Err(message) = &outcome {
+                failures.push(message.clone());
+            }
+            results.push(result(name, "exists", outcome));
+        }
+
+        if !failures.is_empty() {
+            match &rule.default {
+                Some(default) if default != &var.value => {
+                    let mut variable = (*var).clone();
+                    variable.value = default.clone();
+                    plan.push(PlannedFix {
+                        action: "update".to_string(),
+                        id: Some(var.id.clone()),
+                        variable,
+                        reason: failures.join("; "),
+                    });
+                }
+                _ => unfixable.push(name.clone()),
+            }
+        }
+    }
+
+    SchemaReport { results, plan, unfixable }
+}
+
+fn new_variable(scope: &str, name: &str, value: &str, rule: &VariableSchema) -> EnvironmentVariable {
+    EnvironmentVariable {
+        id: format!("{}_{}", scope, name),
+        name: name.to_string(),
+        value: value.to_string(),
+        var_type: scope.to_string(),
+        remark: rule.description.clone(),
+        created_at: 0,
+        updated_at: 0,
+        is_valid: true,
+        sensitive: false,
+        expandable: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, var};
+
+    fn parse(content: &str) -> EnvSchema {
+        toml::from_str(content).unwrap()
+    }
+
+    fn run(schema: &EnvSchema, variables: &[EnvironmentVariable]) -> SchemaReport {
+        let env_map = variables
+            .iter()
+            .map(|var| (var.name.to_uppercase(), var.value.clone()))
+            .collect();
+        check(schema, variables, &env_map, ReferenceSyntax::Windows)
+    }
+
+    fn outcomes(report: &SchemaReport) -> Vec<(&str, &str, bool)> {
+        report
+            .results
+            .iter()
+            .map(|r| (r.variable.as_str(), r.rule_id.as_str(), r.passed))
+            .collect()
+    }
+
+    #[test]
+    fn checks_value_types() {
+        let rule = VariableSchema {
+            values: Some(vec!["dev".to_string(), "prod".to_string()]),
+            ..Default::default()
+        };
+        assert!(check_type("url", &rule, "https://example.com/x", false).is_ok());
+        assert!(check_type("url", &rule, "example.com", false).is_err());
+        assert!(check_type("integer", &rule, " 42 ", false).is_ok());
+        assert!(check_type("integer", &rule, "4.2", false).is_err());
+        assert!(check_type("bool", &rule, "Yes", false).is_ok());
+        assert!(check_type("bool", &rule, "maybe", false).is_err());
+        assert!(check_type("enum", &rule, "prod", false).is_ok());
+        assert_eq!(check_type("enum", &rule, "Prod", false).unwrap_err(), "'Prod' 不在可选值 [dev, prod] 中");
+        assert_eq!(check_type("number", &rule, "1", false).unwrap_err(), "未知的类型 'number'");
+    }
+
+    #[test]
+    fn checks_absolute_windows_paths() {
+        let rule = VariableSchema::default();
+        for value in ["C:\\jdk", "d:/tools", "\\\\server\\share", "%JAVA_HOME%\\bin"] {
+            assert!(check_type("path", &rule, value, false).is_ok(), "{}", value);
+        }
+        assert_eq!(check_type("path", &rule, "  ", false).unwrap_err(), "'  ' 不是有效的路径: 路径为空");
+        assert_eq!(check_type("path", &rule, "relative", false).unwrap_err(), "'relative' 不是有效的路径: 不是绝对路径");
+        assert!(check_type("path", &rule, "C:\\a|b", false).is_err());
+    }
+
+    #[test]
+    fn hides_sensitive_values_in_failure_messages() {
+        let rule = VariableSchema::default();
+        let message = check_type("integer", &rule, "s3cret", true).unwrap_err();
+        assert_eq!(message, format!("{} 不是整数", sensitive::MASK));
+
+        let schema = parse(
+            r#"
+            [variables.API_KEY]
+            type = "url"
+            must_exist = true
+            "#,
+        );
+        let mut key = var("user", "API_KEY", "s3cret");
+        key.sensitive = true;
+        let report = run(&schema, &[key]);
+        assert!(report.results.iter().all(|r| !r.message.contains("s3cret")));
+    }
+
+    #[test]
+    fn plans_missing_required_variables_and_skips_optional_ones() {
+        let schema = parse(
+            r#"
+            [variables.APP_ENV]
+            required = true
+            default = "dev"
+            description = "部署环境"
+
+            [variables.APP_TOKEN]
+            required = true
+
+            [variables.APP_DEBUG]
+            type = "bool"
+            "#,
+        );
+        let report = run(&schema, &[]);
+
+        assert_eq!(
+            outcomes(&report),
+            vec![("APP_DEBUG", "required", true), ("APP_ENV", "required", false), ("APP_TOKEN", "required", false)]
+        );
+        assert_eq!(report.plan.len(), 1);
+        assert_eq!(report.plan[0].action, "add");
+        assert_eq!(report.plan[0].variable.id, "user_APP_ENV");
+        assert_eq!(report.plan[0].variable.remark.as_deref(), Some("部署环境"));
+        assert_eq!(report.unfixable, vec!["APP_TOKEN".to_string()]);
+    }
+
+    #[test]
+    fn matches_names_case_insensitively_within_the_declared_scope() {
+        let schema = parse(
+            r#"
+            [variables.JAVA_HOME]
+            scope = "system"
+            default = "C:\\jdk"
+            "#,
+        );
+        let report = run(&schema, &[var("user", "java_home", "C:\\jdk")]);
+        assert_eq!(outcomes(&report), vec![("JAVA_HOME", "scope", false)]);
+        assert_eq!(report.plan[0].variable.id, "system_JAVA_HOME");
+
+        let report = run(&schema, &[var("system", "java_home", "C:\\jdk")]);
+        assert_eq!(outcomes(&report), vec![("JAVA_HOME", "required", true)]);
+        assert!(report.plan.is_empty());
+    }
+
+    #[test]
+    fn plans_updates_for_values_that_break_constraints() {
+        let schema = parse(
+            r#"
+            [variables.PORT]
+            type = "integer"
+            pattern = "^[0-9]{4}$"
+            default = "8080"
+
+            [variables.LEVEL]
+            type = "enum"
+            values = ["info", "debug"]
+
+            [variables.BROKEN]
+            pattern = "("
+            "#,
+        );
+        let variables = vec![var("user", "PORT", "80x"), var("user", "LEVEL", "trace"), var("user", "BROKEN", "x")];
+        let report = run(&schema, &variables);
+
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                ("BROKEN", "required", true),
+                ("BROKEN", "pattern", false),
+                ("LEVEL", "required", true),
+                ("LEVEL", "type", false),
+                ("PORT", "required", true),
+                ("PORT", "type", false),
+                ("PORT", "pattern", false),
+            ]
+        );
+        assert!(report.results.iter().filter(|r| !r.passed).all(|r| r.severity == Severity::Error));
+        assert_eq!(report.plan.len(), 1);
+        assert_eq!(report.plan[0].action, "update");
+        assert_eq!(report.plan[0].id.as_deref(), Some("user_PORT"));
+        assert_eq!(report.plan[0].variable.value, "8080");
+        assert_eq!(report.plan[0].reason, "'80x' 不是整数; PORT 的值不匹配 ^[0-9]{4}$");
+        assert_eq!(report.unfixable, vec!["BROKEN".to_string(), "LEVEL".to_string()]);
+    }
+
+    #[test]
+    fn checks_that_expanded_paths_exist() {
+        let dir = temp_dir("schema-exists");
+        let schema = parse(
+            r#"
+            [variables.APP_DIR]
+            must_exist = true
+
+            [variables.APP_DATA]
+            must_exist = true
+            "#,
+        );
+        let variables = vec![
+            var("user", "ROOT", dir.to_str().unwrap()),
+            var("user", "APP_DIR", "%ROOT%"),
+            var("user", "APP_DATA", "%ROOT%/missing"),
+        ];
+        let report = run(&schema, &variables);
+
+        assert_eq!(
+            outcomes(&report),
+            vec![
+                ("APP_DATA", "required", true),
+                ("APP_DATA", "exists", false),
+                ("APP_DIR", "required", true),
+                ("APP_DIR", "exists", true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,612 @@
+// Search matching, relevance scoring and highlight ranges
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::commands::{DateRange, EnvironmentVariable, SearchQuery};
+use crate::expand::{self, ReferenceSyntax};
+
+// 名称、值、备注关键字之间的组合方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum KeywordMode {
+    #[default]
+    #[serde(rename = "and")]
+    And,
+    #[serde(rename = "or")]
+    Or,
+}
+
+// 日期范围作用的时间字段
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum DateField {
+    #[serde(rename = "created")]
+    Created,
+    #[default]
+    #[serde(rename = "updated")]
+    Updated,
+    // 创建或修改时间任一落在范围内
+    #[serde(rename = "any")]
+    Any,
+}
+
+// 字段过滤条件的匹配方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum MatchKind {
+    // 不区分大小写的子串
+    #[default]
+    #[serde(rename = "substring")]
+    Substring,
+    // 不区分大小写的通配符（* 和 ?），匹配整个字段
+    #[serde(rename = "glob")]
+    Glob,
+    #[serde(rename = "regex")]
+    Regex,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum FilterField {
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "value")]
+    Value,
+    #[serde(rename = "remark")]
+    Remark,
+    // 名称、值、备注任一字段
+    #[serde(rename = "any")]
+    Any,
+}
+
+// 字段过滤条件，多个条件之间为 AND 关系
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldFilter {
+    pub field: FilterField,
+    pub pattern: String,
+    #[serde(default)]
+    pub kind: MatchKind,
+    #[serde(default)]
+    pub negate: bool,
+}
+
+// 搜索时需要的额外信息
+pub struct SearchContext {
+    pub syntax: ReferenceSyntax,
+    // 展开 PATH 条目时使用的变量映射表（键为大写变量名）
+    pub env_map: HashMap<String, String>,
+    // 变量 ID 到验证结果的映射，查询包含有效性条件时提供
+    pub validity: Option<HashMap<String, bool>>,
+}
+
+// 命中的字段及字符范围（左闭右开，按 Unicode 字符计数）
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Highlight {
+    pub field: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+// PATH 类变量中命中的单个条目，highlights 的 field 为 "raw" 或 "expanded"
+#[derive(Debug, Serialize, Clone)]
+pub struct EntryMatch {
+    pub index: usize,
+    pub raw: String,
+    pub expanded: String,
+    pub highlights: Vec<Highlight>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub variable: EnvironmentVariable,
+    pub score: u32,
+    pub highlights: Vec<Highlight>,
+    // 开启 splitPathEntries 时 PATH 类变量中命中的条目
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<EntryMatch>,
+}
+
+// 以分号分隔多个路径的变量，如 PATH、CLASSPATH、PSModulePath、NODE_PATH
+pub fn is_path_like(name: &str, value: &str) -> bool {
+    let name = name.to_uppercase();
+    name == "PATH" || (name.ends_with("PATH") && value.contains(';'))
+}
+
+// 在 PATH 类变量的每个条目（原始与展开后）中查找值相关的匹配条件
+fn match_entries(
+    query: &SearchQuery,
+    filters: &[(&FieldFilter, Regex)],
+    var: &EnvironmentVariable,
+    ctx: &SearchContext,
+) -> Vec<EntryMatch> {
+    let mut patterns: Vec<&Regex> = filters
+        .iter()
+        .filter(|(filter, _)| !filter.negate && matches!(filter.field, FilterField::Value | FilterField::Any))
+        .map(|(_, regex)| regex)
+        .collect();
+    let keyword = query
+        .value_keyword
+        .as_deref()
+        .filter(|k| !k.is_empty())
+        .and_then(keyword_regex);
+    patterns.extend(keyword.as_ref());
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+
+    let mut expander = expand::Expander::new(&ctx.env_map, ctx.syntax);
+    var.value
+        .split(';')
+        .enumerate()
+        .filter(|(_, raw)| !raw.trim().is_empty())
+        .filter_map(|(index, raw)| {
+            let expanded = expander.expand_as(&var.name, raw).expanded;
+            let mut highlights = Vec::new();
+            for regex in &patterns {
+                for (label, text) in [("raw", raw), ("expanded", expanded.as_str())] {
+                    highlights.extend(regex.find_iter(text).filter(|m| m.start() < m.end()).map(|m| {
+                        let (start, end) = char_range(text, m.start(), m.end());
+                        Highlight { field: label.to_string(), start, end }
+                    }));
+                }
+            }
+            if highlights.is_empty() {
+                return None;
+            }
+            Some(EntryMatch {
+                index,
+                raw: raw.to_string(),
+                expanded,
+                highlights,
+            })
+        })
+        .collect()
+}
+
+fn keyword_regex(keyword: &str) -> Option<Regex> {
+    RegexBuilder::new(&regex::escape(keyword))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+// 把正则匹配的字节范围换算为字符范围，非 ASCII 文本中两者不同
+fn char_range(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let start_chars = text[..start].chars().count();
+    (start_chars, start_chars + text[start..end].chars().count())
+}
+
+// 不区分大小写地查找关键字，返回原始字符串中的字符范围
+pub fn find_ranges(text: &str, keyword: &str) -> Vec<(usize, usize)> {
+    match keyword_regex(keyword) {
+        Some(re) if !keyword.is_empty() => re.find_iter(text).map(|m| char_range(text, m.start(), m.end())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn in_date_range(range: &DateRange, var: &EnvironmentVariable) -> bool {
+    let within = |timestamp: u64| timestamp >= range.start && timestamp <= range.end;
+    match range.field {
+        DateField::Created => within(var.created_at),
+        DateField::Updated => within(var.updated_at),
+        DateField::Any => within(var.created_at) || within(var.updated_at),
+    }
+}
+
+pub fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+// 编译过滤条件
+pub fn compile_filter(filter: &FieldFilter) -> Result<Regex, String> {
+    let result = match filter.kind {
+        MatchKind::Substring => RegexBuilder::new(&regex::escape(&filter.pattern))
+            .case_insensitive(true)
+            .build(),
+        MatchKind::Glob => RegexBuilder::new(&glob_to_regex(&filter.pattern))
+            .case_insensitive(true)
+            .build(),
+        MatchKind::Regex => Regex::new(&filter.pattern),
+    };
+    result.map_err(|e| format!("无效的正则表达式 '{}': {}", filter.pattern, e))
+}
+
+// 应用字段过滤条件，返回命中的字段（取反条件满足时为空）；不满足条件时返回 None
+fn apply_filter(
+    filter: &FieldFilter,
+    regex: &Regex,
+    var: &EnvironmentVariable,
+    remark: &str,
+    expanded: Option<&str>,
+    highlights: &mut Vec<Highlight>,
+) -> Option<Vec<FilterField>> {
+    let fields: &[(FilterField, &str, &str)] = &[
+        (FilterField::Name, "name", &var.name),
+        (FilterField::Value, "value", &var.value),
+        (FilterField::Remark, "remark", remark),
+    ];
+
+    let mut hit_fields = Vec::new();
+    let mut found = Vec::new();
+    for (field, label, text) in fields {
+        if filter.field != FilterField::Any && filter.field != *field {
+            continue;
+        }
+        let expanded_hit = *field == FilterField::Value && expanded.is_some_and(|e| regex.is_match(e));
+        if regex.is_match(text) || expanded_hit {
+            hit_fields.push(*field);
+            // 空匹配（如 .* 匹配空字段）不生成高亮
+            found.extend(regex.find_iter(text).filter(|m| m.start() < m.end()).map(|m| {
+                let (start, end) = char_range(text, m.start(), m.end());
+                Highlight { field: label.to_string(), start, end }
+            }));
+        }
+    }
+
+    if filter.negate {
+        return if hit_fields.is_empty() { Some(Vec::new()) } else { None };
+    }
+    if hit_fields.is_empty() {
+        return None;
+    }
+    highlights.extend(found);
+    Some(hit_fields)
+}
+
+// 单个字段的匹配次数：None 表示该字段没有关键字；expanded 为展开后的值，只参与判断不生成高亮
+fn match_field(
+    field: &str,
+    text: &str,
+    expanded: Option<&str>,
+    keyword: &Option<String>,
+    highlights: &mut Vec<Highlight>,
+) -> Option<usize> {
+    let keyword = keyword.as_deref().filter(|k| !k.is_empty())?;
+    let ranges = find_ranges(text, keyword);
+    highlights.extend(ranges.iter().map(|&(start, end)| Highlight {
+        field: field.to_string(),
+        start,
+        end,
+    }));
+    if ranges.is_empty() && expanded.is_some_and(|e| !find_ranges(e, keyword).is_empty()) {
+        return Some(1);
+    }
+    Some(ranges.len())
+}
+
+// 计算变量是否匹配查询，匹配时返回相关度和高亮
+fn evaluate(
+    query: &SearchQuery,
+    filters: &[(&FieldFilter, Regex)],
+    var: &EnvironmentVariable,
+    ctx: &SearchContext,
+) -> Option<SearchResult> {
+    if let Some(types) = &query.types {
+        if !types.contains(&var.var_type) {
+            return None;
+        }
+    }
+    if let Some(range) = &query.date_range {
+        if !in_date_range(range, var) {
+            return None;
+        }
+    }
+    if let (Some(expected), Some(validity)) = (query.is_valid, &ctx.validity) {
+        if validity.get(&var.id).copied().unwrap_or(true) != expected {
+            return None;
+        }
+    }
+    if let Some(references) = &query.references {
+        let referenced = expand::referenced_names(&var.value, ctx.syntax);
+        if !references.iter().all(|name| referenced.contains(&name.to_uppercase())) {
+            return None;
+        }
+    }
+
+    // 拆分 PATH 条目时，值条件也匹配展开后的值
+    let split = query.split_path_entries && is_path_like(&var.name, &var.value);
+    let expanded = if split {
+        Some(expand::Expander::new(&ctx.env_map, ctx.syntax).expand_as(&var.name, &var.value).expanded)
+    } else {
+        None
+    };
+
+    let mut highlights = Vec::new();
+    let remark = var.remark.clone().unwrap_or_default();
+    let name_hits = match_field("name", &var.name, None, &query.name_keyword, &mut highlights);
+    let value_hits = match_field("value", &var.value, expanded.as_deref(), &query.value_keyword, &mut highlights);
+    let remark_hits = match_field("remark", &remark, None, &query.remark_keyword, &mut highlights);
+
+    let fields: Vec<usize> = [name_hits, value_hits, remark_hits].into_iter().flatten().collect();
+    let keyword_match = match query.keyword_mode {
+        _ if fields.is_empty() => true,
+        KeywordMode::And => fields.iter().all(|&hits| hits > 0),
+        KeywordMode::Or => fields.iter().any(|&hits| hits > 0),
+    };
+    if !keyword_match {
+        return None;
+    }
+
+    let mut score = 0;
+    for (filter, regex) in filters {
+        for field in apply_filter(filter, regex, var, &remark, expanded.as_deref(), &mut highlights)? {
+            score += match field {
+                FilterField::Name => 30,
+                FilterField::Value => 10,
+                _ => 5,
+            };
+        }
+    }
+
+    if let (Some(hits), Some(keyword)) = (name_hits, &query.name_keyword) {
+        if var.name.eq_ignore_ascii_case(keyword) {
+            score += 100;
+        } else if var.name.to_lowercase().starts_with(&keyword.to_lowercase()) {
+            score += 60;
+        } else if hits > 0 {
+            score += 30;
+        }
+    }
+    if let Some(hits) = value_hits.filter(|&hits| hits > 0) {
+        score += 10 + hits.min(5) as u32;
+    }
+    if remark_hits.unwrap_or(0) > 0 {
+        score += 5;
+    }
+
+    let entries = if split {
+        match_entries(query, filters, var, ctx)
+    } else {
+        Vec::new()
+    };
+
+    Some(SearchResult {
+        variable: var.clone(),
+        score,
+        highlights,
+        entries,
+    })
+}
+
+// 按相关度从高到低排序，相同时按名称排序
+pub fn search(query: &SearchQuery, variables: &[EnvironmentVariable], ctx: &SearchContext) -> Result<Vec<SearchResult>, String> {
+    let filters = query
+        .filters
+        .iter()
+        .map(|filter| compile_filter(filter).map(|regex| (filter, regex)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut results: Vec<SearchResult> = variables
+        .iter()
+        .filter_map(|var| evaluate(query, &filters, var, ctx))
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.variable.name.to_lowercase().cmp(&b.variable.name.to_lowercase()))
+    });
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn query(json: serde_json::Value) -> SearchQuery {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn dated(name: &str, created_at: u64, updated_at: u64) -> EnvironmentVariable {
+        let mut variable = var("user", name, "");
+        variable.created_at = created_at;
+        variable.updated_at = updated_at;
+        variable
+    }
+
+    fn context(variables: &[EnvironmentVariable], validity: Option<HashMap<String, bool>>) -> SearchContext {
+        SearchContext {
+            syntax: ReferenceSyntax::Windows,
+            env_map: variables
+                .iter()
+                .map(|var| (var.name.to_uppercase(), var.value.clone()))
+                .collect(),
+            validity,
+        }
+    }
+
+    fn run(query: &SearchQuery, variables: &[EnvironmentVariable]) -> Vec<SearchResult> {
+        search(query, variables, &context(variables, None)).unwrap()
+    }
+
+    fn names(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.variable.name.as_str()).collect()
+    }
+
+    #[test]
+    fn date_range_bounds_are_inclusive_and_default_to_updated() {
+        let variables = vec![dated("BEFORE", 100, 99), dated("START", 0, 100), dated("END", 0, 200), dated("AFTER", 100, 201)];
+        let results = run(&query(serde_json::json!({ "dateRange": { "start": 100, "end": 200 } })), &variables);
+        assert_eq!(names(&results), vec!["END", "START"]);
+
+        let results = run(
+            &query(serde_json::json!({ "dateRange": { "start": 100, "end": 200, "field": "created" } })),
+            &variables,
+        );
+        assert_eq!(names(&results), vec!["AFTER", "BEFORE"]);
+
+        let results = run(
+            &query(serde_json::json!({ "dateRange": { "start": 100, "end": 200, "field": "any" } })),
+            &variables,
+        );
+        assert_eq!(names(&results), vec!["AFTER", "BEFORE", "END", "START"]);
+    }
+
+    #[test]
+    fn combines_field_keywords_with_and_or_or() {
+        let mut tools = var("user", "TOOLS", "C:\\tools");
+        tools.remark = Some("build tools".to_string());
+        let variables = vec![tools, var("user", "JAVA_HOME", "C:\\jdk"), var("system", "TOOLS_DIR", "D:\\bin")];
+
+        let results = run(&query(serde_json::json!({ "nameKeyword": "tools", "valueKeyword": "c:" })), &variables);
+        assert_eq!(names(&results), vec!["TOOLS"]);
+
+        let results = run(
+            &query(serde_json::json!({ "nameKeyword": "tools", "valueKeyword": "c:", "keywordMode": "or" })),
+            &variables,
+        );
+        assert_eq!(names(&results), vec!["TOOLS", "TOOLS_DIR", "JAVA_HOME"]);
+
+        // 空关键字不参与匹配
+        let results = run(&query(serde_json::json!({ "nameKeyword": "", "types": ["system"] })), &variables);
+        assert_eq!(names(&results), vec!["TOOLS_DIR"]);
+
+        let results = run(&query(serde_json::json!({ "remarkKeyword": "BUILD" })), &variables);
+        assert_eq!(names(&results), vec!["TOOLS"]);
+    }
+
+    #[test]
+    fn ranks_exact_then_prefix_then_substring_name_matches() {
+        let variables = vec![var("user", "MY_PATH", ""), var("user", "PATHEXT", ""), var("user", "Path", "")];
+        let results = run(&query(serde_json::json!({ "nameKeyword": "path" })), &variables);
+
+        assert_eq!(names(&results), vec!["Path", "PATHEXT", "MY_PATH"]);
+        let scores: Vec<u32> = results.iter().map(|r| r.score).collect();
+        assert_eq!(scores, vec![100, 60, 30]);
+    }
+
+    #[test]
+    fn highlights_every_match_in_each_field() {
+        let mut variable = var("user", "JAVA_HOME", "C:\\java\\JAVA17");
+        variable.remark = Some("Java".to_string());
+        let result = evaluate(
+            &query(serde_json::json!({ "nameKeyword": "java", "valueKeyword": "java", "remarkKeyword": "java" })),
+            &[],
+            &variable,
+            &context(&[], None),
+        )
+        .unwrap();
+
+        let highlights: Vec<(&str, usize, usize)> = result
+            .highlights
+            .iter()
+            .map(|h| (h.field.as_str(), h.start, h.end))
+            .collect();
+        assert_eq!(highlights, vec![("name", 0, 4), ("value", 3, 7), ("value", 8, 12), ("remark", 0, 4)]);
+        assert_eq!(result.score, 60 + 10 + 2 + 5);
+    }
+
+    #[test]
+    fn reports_highlight_ranges_in_characters() {
+        let mut variable = var("user", "TOOLS", "D:\\工具\\Node");
+        variable.remark = Some("前端工具".to_string());
+        let filters = serde_json::json!([{ "field": "remark", "pattern": "工具", "kind": "substring" }]);
+        let results = run(&query(serde_json::json!({ "valueKeyword": "node", "filters": filters })), &[variable]);
+
+        let highlights: Vec<(&str, usize, usize)> = results[0]
+            .highlights
+            .iter()
+            .map(|h| (h.field.as_str(), h.start, h.end))
+            .collect();
+        assert_eq!(highlights, vec![("value", 6, 10), ("remark", 2, 4)]);
+    }
+
+    #[test]
+    fn applies_glob_regex_and_negated_filters() {
+        let variables = vec![
+            var("user", "JAVA_HOME", "C:\\jdk"),
+            var("user", "JAVA_OPTS", "-Xmx1g"),
+            var("user", "MY_JAVA", "C:\\tmp\\java"),
+        ];
+        let filters = serde_json::json!([
+            { "field": "name", "pattern": "java_*", "kind": "glob" },
+            { "field": "value", "pattern": "^C:", "kind": "regex" },
+            { "field": "any", "pattern": "tmp", "negate": true },
+        ]);
+        let results = run(&query(serde_json::json!({ "filters": filters })), &variables);
+        assert_eq!(names(&results), vec!["JAVA_HOME"]);
+        assert_eq!(results[0].score, 30 + 10);
+
+        // 通配符匹配整个字段
+        let results = run(&query(serde_json::json!({ "filters": [{ "field": "name", "pattern": "JAVA", "kind": "glob" }] })), &variables);
+        assert!(results.is_empty());
+
+        let invalid = query(serde_json::json!({ "filters": [{ "field": "value", "pattern": "(", "kind": "regex" }] }));
+        assert!(search(&invalid, &variables, &context(&variables, None)).is_err());
+    }
+
+    #[test]
+    fn filters_by_references_and_validity() {
+        let variables = vec![
+            var("user", "JAVA_HOME", "C:\\jdk"),
+            var("user", "Path", "%java_home%\\bin;%WINDIR%"),
+            var("user", "BROKEN", "%BROKEN%"),
+        ];
+        let results = run(&query(serde_json::json!({ "references": ["JAVA_HOME", "windir"] })), &variables);
+        assert_eq!(names(&results), vec!["Path"]);
+
+        let ctx = context(&variables, Some(HashMap::from([("user_BROKEN".to_string(), false)])));
+        let results = search(&query(serde_json::json!({ "isValid": false })), &variables, &ctx).unwrap();
+        assert_eq!(names(&results), vec!["BROKEN"]);
+        let results = search(&query(serde_json::json!({ "isValid": true })), &variables, &ctx).unwrap();
+        assert_eq!(names(&results), vec!["JAVA_HOME", "Path"]);
+    }
+
+    #[test]
+    fn detects_path_like_variables() {
+        assert!(is_path_like("Path", "C:\\bin"));
+        assert!(is_path_like("PSModulePath", "C:\\a;C:\\b"));
+        assert!(!is_path_like("PSModulePath", "C:\\a"));
+        assert!(!is_path_like("PATHEXT", ".COM;.EXE"));
+    }
+
+    #[test]
+    fn reports_matching_path_entries_with_raw_and_expanded_highlights() {
+        let variables = vec![
+            var("user", "NODE_HOME", "C:\\node"),
+            var("user", "Path", "C:\\Windows;;%NODE_HOME%\\bin;C:\\node-tools"),
+        ];
+        let results = run(&query(serde_json::json!({ "valueKeyword": "node", "splitPathEntries": true })), &variables);
+        // Path 的值中有两处命中，排在前面
+        assert_eq!(names(&results), vec!["Path", "NODE_HOME"]);
+        assert!(results[1].entries.is_empty());
+
+        let entries: Vec<(usize, &str, &str)> = results[0]
+            .entries
+            .iter()
+            .map(|e| (e.index, e.raw.as_str(), e.expanded.as_str()))
+            .collect();
+        assert_eq!(entries, vec![(2, "%NODE_HOME%\\bin", "C:\\node\\bin"), (3, "C:\\node-tools", "C:\\node-tools")]);
+        let highlights: Vec<(&str, usize, usize)> = results[0].entries[0]
+            .highlights
+            .iter()
+            .map(|h| (h.field.as_str(), h.start, h.end))
+            .collect();
+        assert_eq!(highlights, vec![("raw", 1, 5), ("expanded", 3, 7)]);
+    }
+
+    #[test]
+    fn matches_expanded_path_values_only_when_splitting() {
+        let variables = vec![var("user", "JDK", "C:\\jdk17"), var("user", "Path", "%JDK%\\bin;C:\\Windows")];
+
+        let results = run(&query(serde_json::json!({ "valueKeyword": "jdk17" })), &variables);
+        assert_eq!(names(&results), vec!["JDK"]);
+
+        let results = run(&query(serde_json::json!({ "valueKeyword": "jdk17", "splitPathEntries": true })), &variables);
+        assert_eq!(names(&results), vec!["JDK", "Path"]);
+        // 只在展开后命中时不生成值的高亮
+        assert!(results[1].highlights.is_empty());
+        assert_eq!(results[1].entries.len(), 1);
+
+        let filters = serde_json::json!([{ "field": "value", "pattern": "jdk1?", "kind": "regex", "negate": false }]);
+        let results = run(&query(serde_json::json!({ "filters": filters, "splitPathEntries": true })), &variables);
+        assert_eq!(results[1].entries[0].index, 0);
+    }
+}
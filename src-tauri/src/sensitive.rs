@@ -0,0 +1,119 @@
+// Sensitive variable classification and value masking
+use regex::RegexBuilder;
+
+use crate::commands::EnvironmentVariable;
+use crate::search::{glob_to_regex, SearchResult};
+use crate::validation::Finding;
+
+// 列表、搜索结果中代替敏感值显示的内容
+pub const MASK: &str = "••••••••";
+
+// 按名称识别敏感变量的通配符（不区分大小写）；单个变量可以在元数据中覆盖
+pub const SENSITIVE_NAME_PATTERNS: [&str; 4] = ["*_TOKEN", "*_SECRET", "*PASSWORD*", "AWS_SECRET_ACCESS_KEY"];
+
+// 变量名是否匹配敏感模式
+pub fn is_sensitive_name(name: &str) -> bool {
+    SENSITIVE_NAME_PATTERNS.iter().any(|pattern| {
+        RegexBuilder::new(&glob_to_regex(pattern))
+            .case_insensitive(true)
+            .build()
+            .map(|regex| regex.is_match(name))
+            .unwrap_or(false)
+    })
+}
+
+// 隐藏敏感变量的值
+pub fn mask(variables: &mut [EnvironmentVariable]) {
+    for var in variables.iter_mut().filter(|var| var.sensitive) {
+        var.value = MASK.to_string();
+    }
+}
+
+// 隐藏敏感变量的值，revealed 中的变量（调用方已显示其值）除外
+pub fn mask_except(variables: &mut [EnvironmentVariable], revealed: &[String]) {
+    for var in variables.iter_mut().filter(|var| var.sensitive && !revealed.contains(&var.id)) {
+        var.value = MASK.to_string();
+    }
+}
+
+// 隐藏搜索结果中敏感变量的值，并去掉值上的命中位置和 PATH 条目
+pub fn mask_results(results: &mut [SearchResult]) {
+    for result in results.iter_mut().filter(|result| result.variable.sensitive) {
+        result.variable.value = MASK.to_string();
+        result.highlights.retain(|highlight| highlight.field != "value");
+        result.entries.clear();
+    }
+}
+
+// 去掉敏感变量验证结果中出现的值：原值、展开后的值以及其中 ; 分隔的每一段都替换为 MASK
+pub fn redact_findings(findings: &mut [Finding], value: &str, expanded: &str) {
+    let mut secrets: Vec<&str> = [value, expanded].into_iter()
+        .chain(value.split(';'))
+        .chain(expanded.split(';'))
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+        .collect();
+    // 先替换较长的内容，避免片段替换打断完整的值
+    secrets.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    secrets.dedup();
+
+    let redact = |text: &mut String| {
+        for secret in &secrets {
+            if text.contains(secret) {
+                *text = text.replace(secret, MASK);
+            }
+        }
+    };
+    for finding in findings.iter_mut() {
+        redact(&mut finding.message);
+        if let Some(suggestion) = finding.suggestion.as_mut() {
+            redact(suggestion);
+        }
+        if let Some(expected_path) = finding.expected_path.as_mut() {
+            redact(expected_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    #[test]
+    fn matches_sensitive_name_patterns_case_insensitively() {
+        for name in ["GITHUB_TOKEN", "app_secret", "DB_PASSWORD_FILE", "aws_secret_access_key"] {
+            assert!(is_sensitive_name(name), "{}", name);
+        }
+        for name in ["TOKEN", "SECRET_KEY", "PATH", "AWS_ACCESS_KEY_ID"] {
+            assert!(!is_sensitive_name(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn masks_only_sensitive_variables() {
+        let mut token = var("user", "API_TOKEN", "s3cret");
+        token.sensitive = true;
+        let mut variables = vec![token, var("user", "PLAIN", "ok")];
+        mask(&mut variables);
+
+        let values: Vec<&str> = variables.iter().map(|v| v.value.as_str()).collect();
+        assert_eq!(values, vec![MASK, "ok"]);
+    }
+
+    #[test]
+    fn redacts_values_from_findings() {
+        use crate::validation::Severity;
+
+        let mut findings = vec![
+            Finding::new("path.entries-exist", Severity::Warning, "路径不存在或无法访问: C:\\s3cret\\bin".to_string())
+                .with_suggestion("从PATH中移除 %VAULT%\\bin".to_string()),
+            Finding::new("value.empty", Severity::Info, "变量值为空".to_string()),
+        ];
+        redact_findings(&mut findings, "%VAULT%\\bin;D:\\keys", "C:\\s3cret\\bin;D:\\keys");
+
+        assert_eq!(findings[0].message, format!("路径不存在或无法访问: {}", MASK));
+        assert_eq!(findings[0].suggestion.as_deref(), Some(format!("从PATH中移除 {}", MASK).as_str()));
+        assert_eq!(findings[1].message, "变量值为空");
+    }
+}
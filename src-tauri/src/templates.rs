@@ -0,0 +1,474 @@
+// Parameterized variable templates, stored in templates.json next to settings.json
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::{get_settings_dir, EnvironmentVariable};
+use crate::schema::{self, VariableSchema};
+use crate::sensitive;
+use crate::validation::{self, Finding, Severity};
+
+// 名称和值中的占位符写作 {{PROJECT}}，可加过滤器 {{PROJECT|upper}}、{{PROJECT|lower}}
+const PLACEHOLDER_PATTERN: &str = r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*(?:\|\s*([A-Za-z]+)\s*)?\}\}";
+
+// 占位符及其取值规则；type 与 env-schema.toml 相同（string、path、url、integer、bool、enum）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Placeholder {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub value: String,
+    // "user" 或 "system"，默认 user
+    #[serde(default = "default_scope")]
+    pub scope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+}
+
+fn default_scope() -> String {
+    "user".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Template {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub placeholders: Vec<Placeholder>,
+    pub variables: Vec<TemplateVariable>,
+    #[serde(rename = "createdAt", default)]
+    pub created_at: u64,
+    #[serde(rename = "updatedAt", default)]
+    pub updated_at: u64,
+}
+
+// 模板参数：已保存模板的名称或完整的模板
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateInput {
+    Name(String),
+    Inline(Template),
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct TemplateStore {
+    #[serde(default)]
+    pub templates: Vec<Template>,
+}
+
+fn store_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("templates.json"))
+}
+
+impl TemplateStore {
+    pub fn load() -> Result<Self, String> {
+        let path = store_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read templates: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse templates: {}", e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+        fs::write(store_path()?, content)
+            .map_err(|e| format!("Failed to write templates: {}", e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Template, String> {
+        self.templates
+            .iter()
+            .find(|template| template.name == name)
+            .ok_or_else(|| format!("模板 '{}' 不存在", name))
+    }
+
+    // 按名称新增或替换模板，保留原来的创建时间
+    pub fn upsert(&mut self, mut template: Template, now: u64) -> Result<Template, String> {
+        template.name = template.name.trim().to_string();
+        let problems = check_template(&template);
+        if !problems.is_empty() {
+            return Err(problems.join("; "));
+        }
+
+        template.updated_at = now;
+        match self.templates.iter_mut().find(|existing| existing.name == template.name) {
+            Some(existing) => {
+                template.created_at = existing.created_at;
+                *existing = template.clone();
+            }
+            None => {
+                template.created_at = now;
+                self.templates.push(template.clone());
+            }
+        }
+        Ok(template)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        let before = self.templates.len();
+        self.templates.retain(|template| template.name != name);
+        if self.templates.len() == before {
+            return Err(format!("模板 '{}' 不存在", name));
+        }
+        Ok(())
+    }
+}
+
+// 模板本身的问题：名称为空、占位符重复或未声明、默认值不符合规则等
+pub fn check_template(template: &Template) -> Vec<String> {
+    let mut problems = Vec::new();
+    if template.name.is_empty() {
+        problems.push("模板名称不能为空".to_string());
+    }
+    if template.variables.is_empty() {
+        problems.push("模板中没有任何变量".to_string());
+    }
+
+    let name_re = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    let mut declared = HashSet::new();
+    for placeholder in &template.placeholders {
+        if !name_re.is_match(&placeholder.name) {
+            problems.push(format!("占位符名称 '{}' 只能包含字母、数字和下划线", placeholder.name));
+        }
+        if !declared.insert(placeholder.name.as_str()) {
+            problems.push(format!("占位符 {} 重复声明", placeholder.name));
+        }
+        if let Some(pattern) = &placeholder.pattern {
+            if let Err(e) = Regex::new(pattern) {
+                problems.push(format!("占位符 {} 的正则表达式无效: {}", placeholder.name, e));
+            }
+        }
+        if let Some(default) = &placeholder.default {
+            if let Err(e) = check_param(placeholder, default, is_sensitive_placeholder(template, &placeholder.name)) {
+                problems.push(format!("占位符 {} 的默认值无效: {}", placeholder.name, e));
+            }
+        }
+    }
+
+    let re = Regex::new(PLACEHOLDER_PATTERN).unwrap();
+    for var in &template.variables {
+        if var.scope != "user" && var.scope != "system" {
+            problems.push(format!("{} 的作用域 '{}' 无效，应为 user 或 system", var.name, var.scope));
+        }
+        for text in [Some(&var.name), Some(&var.value), var.remark.as_ref()].into_iter().flatten() {
+            for caps in re.captures_iter(text) {
+                if !declared.contains(&caps[1]) {
+                    problems.push(format!("{} 使用了未声明的占位符 {}", var.name, &caps[1]));
+                }
+                if let Some(filter) = caps.get(2).map(|m| m.as_str()) {
+                    if filter != "upper" && filter != "lower" {
+                        problems.push(format!("{} 使用了未知的过滤器 '{}'，应为 upper 或 lower", var.name, filter));
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+// 占位符出现在敏感变量（按名称判断）的值中时，错误信息中不显示参数值
+fn is_sensitive_placeholder(template: &Template, name: &str) -> bool {
+    let re = Regex::new(PLACEHOLDER_PATTERN).unwrap();
+    template.variables.iter().any(|var| {
+        sensitive::is_sensitive_name(&var.name) && re.captures_iter(&var.value).any(|caps| &caps[1] == name)
+    })
+}
+
+// 按占位符的规则检查参数值
+fn check_param(placeholder: &Placeholder, value: &str, sensitive: bool) -> Result<(), String> {
+    let shown = schema::shown_value(value, sensitive);
+    if let Some(value_type) = &placeholder.value_type {
+        let rule = VariableSchema {
+            values: placeholder.values.clone(),
+            ..Default::default()
+        };
+        schema::check_type(value_type, &rule, value, sensitive)?;
+    } else if let Some(values) = &placeholder.values {
+        if !values.iter().any(|v| v == value) {
+            return Err(format!("{} 不在可选值 [{}] 中", shown, values.join(", ")));
+        }
+    }
+    if let Some(pattern) = &placeholder.pattern {
+        let re = Regex::new(pattern).map_err(|e| format!("无效的正则表达式 '{}': {}", pattern, e))?;
+        if !re.is_match(value) {
+            return Err(format!("{} 不匹配 {}", shown, pattern));
+        }
+    }
+    Ok(())
+}
+
+// 替换文本中的占位符
+fn render(text: &str, params: &BTreeMap<String, String>) -> String {
+    let re = Regex::new(PLACEHOLDER_PATTERN).unwrap();
+    re.replace_all(text, |caps: &regex::Captures| {
+        let value = params.get(&caps[1]).cloned().unwrap_or_default();
+        match caps.get(2).map(|m| m.as_str()) {
+            Some("upper") => value.to_uppercase(),
+            Some("lower") => value.to_lowercase(),
+            _ => value,
+        }
+    })
+    .to_string()
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlanStatus {
+    New,
+    // 已存在同名变量且值相同，不需要写入
+    Identical,
+    // 已存在同名变量但值不同，或与计划中的其他变量同名
+    Collision,
+    // 名称或值不合法
+    Invalid,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PlannedVariable {
+    pub variable: EnvironmentVariable,
+    pub status: PlanStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing: Option<EnvironmentVariable>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TemplatePlan {
+    pub template: String,
+    // 实际使用的参数（包含默认值）
+    pub params: BTreeMap<String, String>,
+    // 参数或模板本身的问题，存在时不能写入
+    pub errors: Vec<String>,
+    pub items: Vec<PlannedVariable>,
+    // 没有错误、冲突和不合法的变量时才可以写入
+    pub ready: bool,
+}
+
+// 用参数实例化模板，生成要创建的变量并与现有变量比较；不写入任何内容
+pub fn instantiate(template: &Template, params: &HashMap<String, String>, current: &[EnvironmentVariable]) -> TemplatePlan {
+    let mut errors = check_template(template);
+
+    let mut resolved = BTreeMap::new();
+    for placeholder in &template.placeholders {
+        let value = params
+            .get(&placeholder.name)
+            .filter(|value| !value.trim().is_empty())
+            .or(placeholder.default.as_ref());
+        match value {
+            Some(value) => {
+                if let Err(e) = check_param(placeholder, value, is_sensitive_placeholder(template, &placeholder.name)) {
+                    errors.push(format!("参数 {}: {}", placeholder.name, e));
+                }
+                resolved.insert(placeholder.name.clone(), value.clone());
+            }
+            None => errors.push(format!("缺少参数 {}", placeholder.name)),
+        }
+    }
+    let mut unknown: Vec<&String> = params
+        .keys()
+        .filter(|name| !template.placeholders.iter().any(|placeholder| &placeholder.name == *name))
+        .collect();
+    unknown.sort();
+    for name in unknown {
+        errors.push(format!("模板中没有占位符 {}", name));
+    }
+
+    let rendered: Vec<EnvironmentVariable> = template
+        .variables
+        .iter()
+        .map(|var| {
+            let name = render(&var.name, &resolved);
+            let is_sensitive = sensitive::is_sensitive_name(&name);
+            EnvironmentVariable {
+                id: format!("{}_{}", var.scope, name),
+                name,
+                value: render(&var.value, &resolved),
+                var_type: var.scope.clone(),
+                remark: var.remark.as_ref().map(|remark| render(remark, &resolved)),
+                created_at: 0,
+                updated_at: 0,
+                is_valid: true,
+                sensitive: is_sensitive,
+                expandable: false,
+            }
+        })
+        .collect();
+
+    let items: Vec<PlannedVariable> = rendered
+        .iter()
+        .enumerate()
+        .map(|(i, var)| {
+            let existing = current
+                .iter()
+                .find(|existing| existing.var_type == var.var_type && existing.name.eq_ignore_ascii_case(&var.name));
+            let mut findings = validation::check_assignment(&var.name, &var.value, &[]);
+            let duplicate = rendered[..i]
+                .iter()
+                .any(|other| other.var_type == var.var_type && other.name.eq_ignore_ascii_case(&var.name));
+            if duplicate {
+                findings.push(Finding::new(
+                    "template.duplicate",
+                    Severity::Error,
+                    format!("模板中有多个变量实例化为 {}", var.name),
+                ));
+            }
+            let collides = existing.filter(|existing| existing.value != var.value);
+            if let Some(existing) = collides {
+                findings.push(Finding::new(
+                    "template.collision",
+                    Severity::Error,
+                    format!("{} 变量 {} 已存在，值不同", existing.var_type, existing.name),
+                ));
+            }
+
+            let status = if duplicate || collides.is_some() {
+                PlanStatus::Collision
+            } else if findings.iter().any(|finding| finding.severity == Severity::Error) {
+                PlanStatus::Invalid
+            } else if existing.is_some() {
+                PlanStatus::Identical
+            } else {
+                PlanStatus::New
+            };
+            PlannedVariable {
+                variable: var.clone(),
+                status,
+                existing: existing.cloned(),
+                findings,
+            }
+        })
+        .collect();
+
+    let ready = errors.is_empty()
+        && items
+            .iter()
+            .all(|item| matches!(item.status, PlanStatus::New | PlanStatus::Identical));
+    TemplatePlan {
+        template: template.name.clone(),
+        params: resolved,
+        errors,
+        items,
+        ready,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::var;
+
+    fn placeholder(name: &str, default: Option<&str>, value_type: Option<&str>) -> Placeholder {
+        Placeholder {
+            name: name.to_string(),
+            description: None,
+            default: default.map(str::to_string),
+            value_type: value_type.map(str::to_string),
+            pattern: None,
+            values: None,
+        }
+    }
+
+    fn template_variable(name: &str, value: &str) -> TemplateVariable {
+        TemplateVariable {
+            name: name.to_string(),
+            value: value.to_string(),
+            scope: default_scope(),
+            remark: None,
+        }
+    }
+
+    fn project_template() -> Template {
+        Template {
+            name: "project".to_string(),
+            description: None,
+            placeholders: vec![placeholder("PROJECT", None, None), placeholder("ROOT", Some(r"D:\work"), Some("path"))],
+            variables: vec![
+                template_variable("{{PROJECT|upper}}_HOME", r"{{ROOT}}\{{ PROJECT | lower }}"),
+                template_variable("{{PROJECT|upper}}_BIN", r"%{{PROJECT|upper}}_HOME%\bin"),
+            ],
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn renders_placeholders_with_filters_and_defaults() {
+        let plan = instantiate(&project_template(), &params(&[("PROJECT", "Demo")]), &[]);
+        assert!(plan.ready, "{:?}", plan.errors);
+        assert_eq!(plan.params.get("ROOT").map(String::as_str), Some(r"D:\work"));
+
+        let rendered: Vec<(&str, &str)> = plan.items.iter().map(|item| (item.variable.name.as_str(), item.variable.value.as_str())).collect();
+        assert_eq!(rendered, vec![("DEMO_HOME", r"D:\work\demo"), ("DEMO_BIN", r"%DEMO_HOME%\bin")]);
+        assert!(plan.items.iter().all(|item| item.status == PlanStatus::New));
+    }
+
+    #[test]
+    fn compares_with_existing_variables() {
+        let current = vec![var("user", "DEMO_HOME", r"D:\work\demo"), var("user", "demo_bin", "other")];
+        let plan = instantiate(&project_template(), &params(&[("PROJECT", "demo")]), &current);
+        let statuses: Vec<PlanStatus> = plan.items.iter().map(|item| item.status).collect();
+        assert_eq!(statuses, vec![PlanStatus::Identical, PlanStatus::Collision]);
+        assert!(!plan.ready);
+    }
+
+    #[test]
+    fn reports_missing_unknown_and_invalid_params() {
+        let plan = instantiate(&project_template(), &params(&[("ROOT", "relative"), ("EXTRA", "1")]), &[]);
+        assert!(!plan.ready);
+        assert!(plan.errors.iter().any(|e| e == "缺少参数 PROJECT"));
+        assert!(plan.errors.iter().any(|e| e.starts_with("参数 ROOT:")));
+        assert!(plan.errors.iter().any(|e| e == "模板中没有占位符 EXTRA"));
+    }
+
+    #[test]
+    fn hides_sensitive_params_in_errors() {
+        let mut token = placeholder("TOKEN", None, None);
+        token.pattern = Some("^tok_".to_string());
+        let template = Template {
+            placeholders: vec![token],
+            variables: vec![template_variable("API_TOKEN", "{{TOKEN}}")],
+            ..project_template()
+        };
+        let plan = instantiate(&template, &params(&[("TOKEN", "hunter2")]), &[]);
+        assert!(!plan.errors.is_empty());
+        assert!(plan.errors.iter().all(|e| !e.contains("hunter2")), "{:?}", plan.errors);
+        assert!(plan.items[0].variable.sensitive);
+    }
+
+    #[test]
+    fn checks_template_definitions() {
+        let template = Template {
+            placeholders: vec![placeholder("A", None, None), placeholder("A", None, None)],
+            variables: vec![TemplateVariable {
+                scope: "machine".to_string(),
+                ..template_variable("X_{{B}}", "{{A|title}}")
+            }],
+            ..project_template()
+        };
+        let problems = check_template(&template);
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+    }
+}
@@ -0,0 +1,29 @@
+// Shared fixtures for unit tests
+use std::fs;
+use std::path::PathBuf;
+
+use crate::commands::EnvironmentVariable;
+
+// 构造测试用的变量，id 与从注册表读取时一样为 "作用域_名称"
+pub fn var(scope: &str, name: &str, value: &str) -> EnvironmentVariable {
+    EnvironmentVariable {
+        id: format!("{}_{}", scope, name),
+        name: name.to_string(),
+        value: value.to_string(),
+        var_type: scope.to_string(),
+        remark: None,
+        created_at: 0,
+        updated_at: 0,
+        is_valid: true,
+        sensitive: false,
+        expandable: false,
+    }
+}
+
+// 每个测试使用独立的空目录，label 在测试之间不能重复
+pub fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("env-manager-test-{}-{}", std::process::id(), label));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,367 @@
+// Layout validators for well-known SDK and toolchain variables
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::validation::{Finding, Severity, ValidationContext};
+
+// 可执行文件名（Windows 下带 .exe 后缀）
+fn exe(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// 变量名匹配时返回展开后的根目录；目录不存在时交给 path.target-exists 规则处理
+fn toolchain_root(ctx: &ValidationContext, names: &[&str]) -> Option<PathBuf> {
+    if !names.iter().any(|name| ctx.name.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    let root = PathBuf::from(ctx.expand(ctx.value.trim()));
+    if root.is_dir() {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+// 检查根目录下的必需文件，缺失的每个文件生成一条错误
+fn check_required(ctx: &ValidationContext, rule_id: &str, root: &Path, required: &[&str]) -> Vec<Finding> {
+    required
+        .iter()
+        .map(|relative| root.join(relative))
+        .filter(|path| !path.exists())
+        .map(|path| {
+            let expected = path.to_string_lossy().to_string();
+            Finding::new(rule_id, Severity::Error, format!("{} 中缺少 {}", ctx.name, expected))
+                .with_expected_path(expected)
+                .with_suggestion(format!("确认 {} 指向完整的安装目录", ctx.name))
+        })
+        .collect()
+}
+
+// 布局正确时给出检测到的版本信息
+fn detected(ctx: &ValidationContext, rule_id: &str, expected: &Path, version: Option<String>) -> Finding {
+    let message = match &version {
+        Some(version) => format!("{} 指向有效的安装，版本 {}", ctx.name, version),
+        None => format!("{} 指向有效的安装，未能识别版本", ctx.name),
+    };
+    Finding::new(rule_id, Severity::Info, message)
+        .with_expected_path(expected.to_string_lossy().to_string())
+        .with_detected_version(version)
+}
+
+// 读取 key=value 形式文件中的值，去掉引号
+fn read_property(path: &Path, key: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        if k.trim() == key {
+            Some(v.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+// JAVA_HOME 必须包含 bin/java 和 release 文件，版本取自 release 中的 JAVA_VERSION
+pub fn check_java_home(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.java-home";
+    let Some(root) = toolchain_root(ctx, &["JAVA_HOME"]) else {
+        return Vec::new();
+    };
+
+    let java = Path::new("bin").join(exe("java")).to_string_lossy().to_string();
+    let findings = check_required(ctx, rule_id, &root, &[&java, "release"]);
+    if !findings.is_empty() {
+        return findings;
+    }
+
+    let version = read_property(&root.join("release"), "JAVA_VERSION");
+    vec![detected(ctx, rule_id, &root.join(java), version)]
+}
+
+// GOROOT 必须包含 bin/go，版本取自 VERSION 文件首行
+pub fn check_goroot(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.goroot";
+    let Some(root) = toolchain_root(ctx, &["GOROOT"]) else {
+        return Vec::new();
+    };
+
+    let go = Path::new("bin").join(exe("go")).to_string_lossy().to_string();
+    let findings = check_required(ctx, rule_id, &root, &[&go]);
+    if !findings.is_empty() {
+        return findings;
+    }
+
+    let version = fs::read_to_string(root.join("VERSION"))
+        .ok()
+        .and_then(|content| content.lines().next().map(|line| line.trim().to_string()))
+        .filter(|line| !line.is_empty());
+    vec![detected(ctx, rule_id, &root.join(go), version)]
+}
+
+// PYTHONHOME 需要解释器和标准库（os.py），版本从 pythonXY.dll 或 lib/pythonX.Y 推断
+pub fn check_python_home(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.python-home";
+    let Some(root) = toolchain_root(ctx, &["PYTHONHOME"]) else {
+        return Vec::new();
+    };
+
+    if cfg!(windows) {
+        let findings = check_required(ctx, rule_id, &root, &["python.exe", "Lib\\os.py"]);
+        if !findings.is_empty() {
+            return findings;
+        }
+
+        let version = fs::read_dir(&root).ok().and_then(|entries| {
+            entries.flatten().find_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_lowercase();
+                let digits = file_name.strip_prefix("python")?.strip_suffix(".dll")?.to_string();
+                if digits.len() >= 2 && digits.chars().all(|c| c.is_ascii_digit()) {
+                    Some(format!("{}.{}", &digits[..1], &digits[1..]))
+                } else {
+                    None
+                }
+            })
+        });
+        return vec![detected(ctx, rule_id, &root.join("python.exe"), version)];
+    }
+
+    let stdlib = fs::read_dir(root.join("lib")).ok().and_then(|entries| {
+        entries.flatten().find_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let version = file_name.strip_prefix("python")?.to_string();
+            if entry.path().join("os.py").exists() {
+                Some((entry.path(), version))
+            } else {
+                None
+            }
+        })
+    });
+    match stdlib {
+        Some((path, version)) => vec![detected(ctx, rule_id, &path.join("os.py"), Some(version))],
+        None => {
+            let expected = root.join("lib").join("pythonX.Y").join("os.py").to_string_lossy().to_string();
+            vec![Finding::new(rule_id, Severity::Error, format!("{} 中未找到 Python 标准库", ctx.name))
+                .with_expected_path(expected)
+                .with_suggestion(format!("确认 {} 指向 Python 安装前缀", ctx.name))]
+        }
+    }
+}
+
+// CARGO_HOME 必须包含 bin/cargo
+pub fn check_cargo_home(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.cargo-home";
+    let Some(root) = toolchain_root(ctx, &["CARGO_HOME"]) else {
+        return Vec::new();
+    };
+
+    let cargo = Path::new("bin").join(exe("cargo")).to_string_lossy().to_string();
+    let findings = check_required(ctx, rule_id, &root, &[&cargo]);
+    if !findings.is_empty() {
+        return findings;
+    }
+
+    vec![detected(ctx, rule_id, &root.join(cargo), None)]
+}
+
+// RUSTUP_HOME 必须包含 toolchains 目录和 settings.toml，版本为默认工具链
+pub fn check_rustup_home(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.rustup-home";
+    let Some(root) = toolchain_root(ctx, &["RUSTUP_HOME"]) else {
+        return Vec::new();
+    };
+
+    let findings = check_required(ctx, rule_id, &root, &["toolchains", "settings.toml"]);
+    if !findings.is_empty() {
+        return findings;
+    }
+
+    let version = read_property(&root.join("settings.toml"), "default_toolchain");
+    vec![detected(ctx, rule_id, &root.join("settings.toml"), version)]
+}
+
+// ANDROID_HOME/ANDROID_SDK_ROOT 必须包含 platform-tools/adb，版本取自 source.properties
+pub fn check_android_sdk(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.android-sdk";
+    let Some(root) = toolchain_root(ctx, &["ANDROID_HOME", "ANDROID_SDK_ROOT"]) else {
+        return Vec::new();
+    };
+
+    let adb = Path::new("platform-tools").join(exe("adb")).to_string_lossy().to_string();
+    let findings = check_required(ctx, rule_id, &root, &[&adb]);
+    if !findings.is_empty() {
+        return findings;
+    }
+
+    let version = read_property(&root.join("platform-tools").join("source.properties"), "Pkg.Revision");
+    vec![detected(ctx, rule_id, &root.join(adb), version)]
+}
+
+// NODE_PATH 中的每个目录都应存在并包含模块目录（含 package.json 的子目录或 @scope 目录）
+pub fn check_node_path(ctx: &ValidationContext) -> Vec<Finding> {
+    let rule_id = "toolchain.node-path";
+    if !ctx.name.eq_ignore_ascii_case("NODE_PATH") {
+        return Vec::new();
+    }
+
+    let separator = if cfg!(windows) { ';' } else { ':' };
+    let mut findings = Vec::new();
+    for entry in ctx.value.split(separator).map(str::trim).filter(|entry| !entry.is_empty()) {
+        let dir = PathBuf::from(ctx.expand(entry));
+        if !dir.is_dir() {
+            findings.push(
+                Finding::new(rule_id, Severity::Error, format!("NODE_PATH 中的目录不存在: {}", dir.display()))
+                    .with_expected_path(dir.to_string_lossy().to_string()),
+            );
+            continue;
+        }
+
+        let module_count = fs::read_dir(&dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| {
+                        entry.path().join("package.json").exists()
+                            || entry.file_name().to_string_lossy().starts_with('@')
+                    })
+                    .count()
+            })
+            .unwrap_or(0);
+        if module_count == 0 {
+            findings.push(
+                Finding::new(rule_id, Severity::Warning, format!("NODE_PATH 目录中没有 Node 模块: {}", dir.display()))
+                    .with_expected_path(dir.join("<module>").join("package.json").to_string_lossy().to_string())
+                    .with_suggestion("NODE_PATH 通常应指向 node_modules 目录".to_string()),
+            );
+        } else {
+            findings.push(Finding::new(
+                rule_id,
+                Severity::Info,
+                format!("{} 中包含 {} 个模块", dir.display(), module_count),
+            ));
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expand::ReferenceSyntax;
+    use crate::test_support::temp_dir;
+    use std::collections::HashMap;
+
+    fn run(check: fn(&ValidationContext) -> Vec<Finding>, name: &str, value: &str) -> Vec<Finding> {
+        let env_map = HashMap::new();
+        check(&ValidationContext::new(name, value, &env_map, ReferenceSyntax::Windows))
+    }
+
+    fn touch(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn severities(findings: &[Finding]) -> Vec<Severity> {
+        findings.iter().map(|f| f.severity).collect()
+    }
+
+    #[test]
+    fn reports_each_missing_java_home_file() {
+        let root = temp_dir("java-missing");
+        let findings = run(check_java_home, "JAVA_HOME", &root.to_string_lossy());
+        let expected: Vec<String> = findings.iter().filter_map(|f| f.expected_path.clone()).collect();
+        assert_eq!(severities(&findings), vec![Severity::Error, Severity::Error]);
+        assert_eq!(
+            expected,
+            vec![
+                root.join("bin").join(exe("java")).to_string_lossy().to_string(),
+                root.join("release").to_string_lossy().to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_java_version_from_release() {
+        let root = temp_dir("java-valid");
+        touch(&root, &format!("bin/{}", exe("java")), "");
+        touch(&root, "release", "IMPLEMENTOR=\"Eclipse\"\nJAVA_VERSION=\"17.0.2\"\n");
+        let findings = run(check_java_home, "java_home", &root.to_string_lossy());
+        assert_eq!(severities(&findings), vec![Severity::Info]);
+        assert_eq!(findings[0].detected_version.as_deref(), Some("17.0.2"));
+    }
+
+    #[test]
+    fn leaves_missing_roots_and_other_names_to_other_rules() {
+        let root = temp_dir("java-other");
+        assert!(run(check_java_home, "JAVA_HOME", &root.join("missing").to_string_lossy()).is_empty());
+        assert!(run(check_java_home, "JDK_HOME", &root.to_string_lossy()).is_empty());
+    }
+
+    #[test]
+    fn validates_go_cargo_rustup_and_android_layouts() {
+        let go = temp_dir("goroot");
+        touch(&go, &format!("bin/{}", exe("go")), "");
+        touch(&go, "VERSION", "go1.22.1\ntime 2024-03-01\n");
+        let findings = run(check_goroot, "GOROOT", &go.to_string_lossy());
+        assert_eq!(findings[0].detected_version.as_deref(), Some("go1.22.1"));
+
+        let cargo = temp_dir("cargo-home");
+        assert_eq!(severities(&run(check_cargo_home, "CARGO_HOME", &cargo.to_string_lossy())), vec![Severity::Error]);
+        touch(&cargo, &format!("bin/{}", exe("cargo")), "");
+        assert_eq!(severities(&run(check_cargo_home, "CARGO_HOME", &cargo.to_string_lossy())), vec![Severity::Info]);
+
+        let rustup = temp_dir("rustup-home");
+        fs::create_dir_all(rustup.join("toolchains")).unwrap();
+        touch(&rustup, "settings.toml", "default_toolchain = \"stable-x86_64-pc-windows-msvc\"\n");
+        let findings = run(check_rustup_home, "RUSTUP_HOME", &rustup.to_string_lossy());
+        assert_eq!(findings[0].detected_version.as_deref(), Some("stable-x86_64-pc-windows-msvc"));
+
+        let android = temp_dir("android-sdk");
+        touch(&android, &format!("platform-tools/{}", exe("adb")), "");
+        touch(&android, "platform-tools/source.properties", "Pkg.UserSrc=false\nPkg.Revision=35.0.1\n");
+        for name in ["ANDROID_HOME", "ANDROID_SDK_ROOT"] {
+            let findings = run(check_android_sdk, name, &android.to_string_lossy());
+            assert_eq!(findings[0].detected_version.as_deref(), Some("35.0.1"));
+        }
+    }
+
+    #[test]
+    fn detects_python_home_version() {
+        let root = temp_dir("python-home");
+        assert_eq!(severities(&run(check_python_home, "PYTHONHOME", &root.to_string_lossy()))[0], Severity::Error);
+        if cfg!(windows) {
+            touch(&root, "python.exe", "");
+            touch(&root, "Lib/os.py", "");
+            touch(&root, "python312.dll", "");
+        } else {
+            touch(&root, "lib/python3.12/os.py", "");
+        }
+        let findings = run(check_python_home, "PYTHONHOME", &root.to_string_lossy());
+        assert_eq!(severities(&findings), vec![Severity::Info]);
+        assert_eq!(findings[0].detected_version.as_deref(), Some("3.12"));
+    }
+
+    #[test]
+    fn checks_node_path_entries() {
+        let modules = temp_dir("node-modules");
+        touch(&modules, "left-pad/package.json", "{}");
+        fs::create_dir_all(modules.join("@types")).unwrap();
+        let empty = temp_dir("node-empty");
+        let missing = empty.join("missing");
+
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let value = [modules.as_path(), empty.as_path(), missing.as_path()]
+            .iter()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join(separator);
+        let findings = run(check_node_path, "NODE_PATH", &value);
+        assert_eq!(severities(&findings), vec![Severity::Info, Severity::Warning, Severity::Error]);
+        assert!(findings[0].message.contains("2 个模块"));
+    }
+}
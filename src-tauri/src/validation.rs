@@ -0,0 +1,664 @@
+// Rule based validation for environment variable values
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::commands::EnvironmentVariable;
+use crate::expand::{self, ExpansionIssue, ReferenceSyntax};
+use crate::toolchain;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Severity {
+    #[serde(rename = "info")]
+    Info,
+    #[serde(rename = "warning")]
+    Warning,
+    #[serde(rename = "error")]
+    Error,
+}
+
+// 单条验证结果
+#[derive(Debug, Serialize, Clone)]
+pub struct Finding {
+    #[serde(rename = "ruleId")]
+    pub rule_id: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    // 规则期望存在的文件（工具链验证使用）
+    #[serde(rename = "expectedPath", skip_serializing_if = "Option::is_none")]
+    pub expected_path: Option<String>,
+    // 检测到的版本号
+    #[serde(rename = "detectedVersion", skip_serializing_if = "Option::is_none")]
+    pub detected_version: Option<String>,
+}
+
+impl Finding {
+    pub fn new(rule_id: &str, severity: Severity, message: String) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            severity,
+            message,
+            suggestion: None,
+            expected_path: None,
+            detected_version: None,
+        }
+    }
+
+    pub fn with_suggestion(mut self, suggestion: String) -> Self {
+        self.suggestion = Some(suggestion);
+        self
+    }
+
+    pub fn with_expected_path(mut self, path: String) -> Self {
+        self.expected_path = Some(path);
+        self
+    }
+
+    pub fn with_detected_version(mut self, version: Option<String>) -> Self {
+        self.detected_version = version;
+        self
+    }
+}
+
+// 单个变量的验证报告，存在 error 级别结果时视为无效
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationReport {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "isValid")]
+    pub is_valid: bool,
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn new(id: String, name: String, findings: Vec<Finding>) -> Self {
+        Self {
+            is_valid: !findings.iter().any(|f| f.severity == Severity::Error),
+            id,
+            name,
+            findings,
+        }
+    }
+}
+
+// 规则执行时可用的上下文
+pub struct ValidationContext<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub env_map: &'a HashMap<String, String>,
+    pub syntax: ReferenceSyntax,
+    // 变量是否敏感，敏感变量的值不做路径推测
+    pub sensitive: bool,
+    // 变量自身的展开问题，多条规则共用，首次使用时计算
+    issues: OnceCell<Vec<ExpansionIssue>>,
+}
+
+impl<'a> ValidationContext<'a> {
+    pub fn new(name: &'a str, value: &'a str, env_map: &'a HashMap<String, String>, syntax: ReferenceSyntax) -> Self {
+        Self { name, value, env_map, syntax, sensitive: false, issues: OnceCell::new() }
+    }
+
+    pub fn with_sensitive(mut self, sensitive: bool) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+
+    pub fn expand(&self, value: &str) -> String {
+        expand::expand_value(value, self.env_map, self.syntax).expanded
+    }
+
+    fn is_path_variable(&self) -> bool {
+        self.name.eq_ignore_ascii_case("PATH")
+    }
+}
+
+// 命名的验证规则
+#[derive(Serialize)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    pub check: fn(&ValidationContext) -> Vec<Finding>,
+}
+
+// 内置规则列表，按执行顺序排列
+pub fn rules() -> Vec<Rule> {
+    vec![
+        Rule { id: "value.empty", description: "变量值为空", check: check_empty_value },
+        Rule { id: "reference.undefined", description: "引用了未定义的变量", check: check_undefined_references },
+        Rule { id: "reference.cycle", description: "变量之间存在循环引用", check: check_reference_cycles },
+        Rule { id: "path.entries-exist", description: "PATH中的路径必须存在", check: check_path_entries },
+        Rule { id: "path.target-exists", description: "_HOME/_DIR/_PATH 变量或路径值指向的位置必须存在", check: check_path_target },
+        Rule { id: "toolchain.java-home", description: "JAVA_HOME 必须包含 bin/java 和 release 文件", check: toolchain::check_java_home },
+        Rule { id: "toolchain.goroot", description: "GOROOT 必须包含 bin/go", check: toolchain::check_goroot },
+        Rule { id: "toolchain.python-home", description: "PYTHONHOME 必须包含 Python 解释器和标准库", check: toolchain::check_python_home },
+        Rule { id: "toolchain.cargo-home", description: "CARGO_HOME 必须包含 bin/cargo", check: toolchain::check_cargo_home },
+        Rule { id: "toolchain.rustup-home", description: "RUSTUP_HOME 必须包含 toolchains 目录和 settings.toml", check: toolchain::check_rustup_home },
+        Rule { id: "toolchain.android-sdk", description: "ANDROID_HOME/ANDROID_SDK_ROOT 必须包含 platform-tools", check: toolchain::check_android_sdk },
+        Rule { id: "toolchain.node-path", description: "NODE_PATH 中的目录必须包含 Node 模块", check: toolchain::check_node_path },
+    ]
+}
+
+// 对变量执行全部规则
+pub fn validate(ctx: &ValidationContext) -> Vec<Finding> {
+    rules().iter().flat_map(|rule| (rule.check)(ctx)).collect()
+}
+
+// 批量验证的进度，每完成一个变量推送一次
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub id: String,
+    #[serde(rename = "isValid")]
+    pub is_valid: bool,
+}
+
+// 批量验证汇总，variables 中的 isValid 已按验证结果填写
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationSummary {
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    #[serde(rename = "withWarnings")]
+    pub with_warnings: usize,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u128,
+    pub reports: Vec<ValidationReport>,
+    pub variables: Vec<EnvironmentVariable>,
+}
+
+// 使用同一份变量快照并发验证所有变量，并发数不超过 parallelism
+pub fn validate_all<F>(
+    mut variables: Vec<EnvironmentVariable>,
+    env_map: &HashMap<String, String>,
+    syntax: ReferenceSyntax,
+    parallelism: usize,
+    on_progress: F,
+) -> ValidationSummary
+where
+    F: Fn(ValidationProgress) + Sync,
+{
+    let started = Instant::now();
+    let total = variables.len();
+    let next = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<ValidationReport>>> = Mutex::new(vec![None; total]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..parallelism.clamp(1, total.max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(var) = variables.get(index) else {
+                    break;
+                };
+
+                let findings = validate(&ValidationContext::new(&var.name, &var.value, env_map, syntax).with_sensitive(var.sensitive));
+                let report = ValidationReport::new(var.id.clone(), var.name.clone(), findings);
+
+                on_progress(ValidationProgress {
+                    completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                    total,
+                    id: report.id.clone(),
+                    is_valid: report.is_valid,
+                });
+                slots.lock().unwrap()[index] = Some(report);
+            });
+        }
+    });
+
+    let reports: Vec<ValidationReport> = slots.into_inner().unwrap().into_iter().flatten().collect();
+    for (var, report) in variables.iter_mut().zip(&reports) {
+        var.is_valid = report.is_valid;
+    }
+
+    ValidationSummary {
+        total,
+        valid: reports.iter().filter(|r| r.is_valid).count(),
+        invalid: reports.iter().filter(|r| !r.is_valid).count(),
+        with_warnings: reports
+            .iter()
+            .filter(|r| r.findings.iter().any(|f| f.severity == Severity::Warning))
+            .count(),
+        duration_ms: started.elapsed().as_millis(),
+        reports,
+        variables,
+    }
+}
+
+fn check_empty_value(ctx: &ValidationContext) -> Vec<Finding> {
+    if ctx.value.trim().is_empty() {
+        vec![Finding::new("value.empty", Severity::Info, format!("变量 {} 的值为空", ctx.name))]
+    } else {
+        Vec::new()
+    }
+}
+
+fn expansion_issues<'c>(ctx: &'c ValidationContext) -> &'c [ExpansionIssue] {
+    ctx.issues.get_or_init(|| {
+        expand::Expander::new(ctx.env_map, ctx.syntax)
+            .expand_as(ctx.name, ctx.value)
+            .issues
+    })
+}
+
+// 引用了未定义的变量（保留原样，不会被展开）
+fn check_undefined_references(ctx: &ValidationContext) -> Vec<Finding> {
+    expansion_issues(ctx)
+        .iter()
+        .filter_map(|issue| match issue {
+            ExpansionIssue::Undefined { name } => Some(
+                Finding::new("reference.undefined", Severity::Warning, expand::describe_issue(issue))
+                    .with_suggestion(format!("定义变量 {} 或移除该引用", name)),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+// 循环引用无法展开，视为错误
+fn check_reference_cycles(ctx: &ValidationContext) -> Vec<Finding> {
+    expansion_issues(ctx)
+        .iter()
+        .filter(|issue| matches!(issue, ExpansionIssue::Cycle { .. }))
+        .map(|issue| {
+            Finding::new("reference.cycle", Severity::Error, expand::describe_issue(issue))
+                .with_suggestion("修改其中一个变量以打破循环".to_string())
+        })
+        .collect()
+}
+
+// 验证PATH变量中的每个路径，有效路径不足一半时视为错误
+fn check_path_entries(ctx: &ValidationContext) -> Vec<Finding> {
+    if !ctx.is_path_variable() {
+        return Vec::new();
+    }
+
+    let mut findings = Vec::new();
+    let mut valid_count = 0;
+    let mut total_count = 0;
+
+    for path in ctx.value.split(';') {
+        // 空路径跳过验证
+        if path.trim().is_empty() {
+            continue;
+        }
+        total_count += 1;
+
+        let expanded_path = ctx.expand(path.trim());
+        let path_obj = Path::new(&expanded_path);
+        match path_obj.metadata() {
+            Ok(metadata) if metadata.is_dir() => valid_count += 1,
+            Ok(_) => findings.push(
+                Finding::new("path.entries-exist", Severity::Warning, format!("路径不是目录: {}", expanded_path))
+                    .with_suggestion(format!("从PATH中移除 {}", path.trim())),
+            ),
+            Err(_) => findings.push(
+                Finding::new("path.entries-exist", Severity::Warning, format!("路径不存在或无法访问: {}", expanded_path))
+                    .with_suggestion(format!("从PATH中移除 {}", path.trim())),
+            ),
+        }
+    }
+
+    if total_count > 0 && (valid_count as f64 / total_count as f64) < 0.5 {
+        findings.push(Finding::new(
+            "path.entries-exist",
+            Severity::Error,
+            format!("PATH中只有 {}/{} 个路径有效", valid_count, total_count),
+        ));
+    }
+
+    findings
+}
+
+// 值是否以路径开头：盘符（C:\ 或 C:/）、UNC（\\server）、/、~ 或 %VAR%\ 这样的变量前缀
+fn looks_like_path(value: &str) -> bool {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    let is_separator = |b: Option<&u8>| matches!(b, Some(b'\\') | Some(b'/'));
+
+    let drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && is_separator(bytes.get(2));
+    let unc = value.starts_with("\\\\");
+    let rooted = value.starts_with('/') || value.starts_with('~');
+    let variable_prefix = value.starts_with('%')
+        && matches!(value[1..].find('%'), Some(end) if end > 0 && is_separator(bytes.get(end + 2)));
+    drive || unc || rooted || variable_prefix
+}
+
+// 名称以 _HOME、_DIR、_PATH 结尾的变量，目标必须存在；仅值像路径的变量只给出警告，敏感变量不按值推测；
+// ; 分隔的路径列表（如 NODE_PATH=a;b）整体不是一个路径，不在这里检查，NODE_PATH 由工具链规则逐条检查
+fn check_path_target(ctx: &ValidationContext) -> Vec<Finding> {
+    if ctx.is_path_variable() || ctx.value.contains(';') || ctx.name.eq_ignore_ascii_case("NODE_PATH") {
+        return Vec::new();
+    }
+
+    let name = ctx.name.to_uppercase();
+    let by_suffix = name.ends_with("_HOME") || name.ends_with("_DIR") || name.ends_with("_PATH");
+    let by_value = !ctx.sensitive && looks_like_path(ctx.value);
+    if !by_suffix && !by_value {
+        return Vec::new();
+    }
+
+    let expanded_value = ctx.expand(ctx.value);
+    if Path::new(&expanded_value).metadata().is_ok() {
+        return Vec::new();
+    }
+
+    // 消息只写变量名，不带出值本身
+    let reason = if by_suffix {
+        format!("{} 以路径后缀结尾，但指向的路径不存在", ctx.name)
+    } else {
+        format!("{} 的值看起来是路径，但该路径不存在", ctx.name)
+    };
+    let severity = if by_suffix { Severity::Error } else { Severity::Warning };
+    vec![Finding::new("path.target-exists", severity, reason)
+        .with_suggestion("检查路径是否正确，或该程序是否已被卸载".to_string())]
+}
+
+// 注册表值名称的最大长度
+pub const MAX_NAME_LENGTH: usize = 16_383;
+// 单个环境变量（name=value 加结尾 NUL）的最大长度，同时也是整个环境块的上限
+pub const MAX_VARIABLE_LENGTH: usize = 32_767;
+
+// 写入、导入、重命名前的名称与值合法性检查，existing 为同一作用域下已有的变量名
+pub fn check_assignment(name: &str, value: &str, existing: &[String]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if name.is_empty() {
+        findings.push(Finding::new("name.empty", Severity::Error, "变量名不能为空".to_string()));
+        return findings;
+    }
+    if name.contains('=') {
+        findings.push(Finding::new("name.equals", Severity::Error, format!("变量名不能包含 '=': {}", name)));
+    }
+    if name.contains('\0') {
+        findings.push(Finding::new("name.nul", Severity::Error, "变量名不能包含 NUL 字符".to_string()));
+    }
+    if name.trim() != name {
+        findings.push(
+            Finding::new("name.whitespace", Severity::Error, format!("变量名首尾不能包含空白字符: '{}'", name))
+                .with_suggestion(format!("使用 '{}'", name.trim())),
+        );
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        findings.push(Finding::new(
+            "name.too-long",
+            Severity::Error,
+            format!("变量名长度超过注册表限制 {} 个字符", MAX_NAME_LENGTH),
+        ));
+    }
+    if value.contains('\0') {
+        findings.push(Finding::new("value.nul", Severity::Error, "变量值不能包含 NUL 字符".to_string()));
+    }
+
+    let length = name.chars().count() + value.chars().count() + 2;
+    if length > MAX_VARIABLE_LENGTH {
+        findings.push(Finding::new(
+            "value.too-long",
+            Severity::Error,
+            format!("变量长度 {} 超过 {} 个字符的上限", length, MAX_VARIABLE_LENGTH),
+        ));
+    }
+
+    // 仅在部分平台合法的名称
+    let mut chars = name.chars();
+    let portable = chars.next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !portable && findings.is_empty() {
+        findings.push(
+            Finding::new(
+                "name.portable",
+                Severity::Warning,
+                format!("变量名 {} 只在 Windows 上合法，POSIX shell 无法引用", name),
+            )
+            .with_suggestion("仅使用字母、数字和下划线，且不以数字开头".to_string()),
+        );
+    }
+
+    // Windows 下名称不区分大小写，Path 与 PATH 是同一个变量
+    for other in existing {
+        if other != name && other.eq_ignore_ascii_case(name) {
+            findings.push(
+                Finding::new(
+                    "name.case-collision",
+                    Severity::Warning,
+                    format!("{} 与已有变量 {} 仅大小写不同，在 Windows 上会覆盖该变量，在区分大小写的平台上则是两个变量", name, other),
+                )
+                .with_suggestion(format!("使用已有名称 {}", other)),
+            );
+        }
+    }
+
+    findings
+}
+
+// 存在 error 级别结果时拒绝写入
+pub fn ensure_assignment_allowed(name: &str, value: &str, existing: &[String]) -> Result<Vec<Finding>, String> {
+    let findings = check_assignment(name, value, existing);
+    let errors: Vec<&str> = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .map(|f| f.message.as_str())
+        .collect();
+    if errors.is_empty() {
+        Ok(findings)
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, var};
+    use std::fs;
+
+    fn check(name: &str, value: &str, env_map: &HashMap<String, String>) -> Vec<Finding> {
+        validate(&ValidationContext::new(name, value, env_map, ReferenceSyntax::Windows))
+    }
+
+    fn summary(findings: &[Finding]) -> Vec<(&str, Severity)> {
+        findings.iter().map(|f| (f.rule_id.as_str(), f.severity)).collect()
+    }
+
+    #[test]
+    fn rule_ids_are_unique() {
+        let ids: Vec<&str> = rules().iter().map(|rule| rule.id).collect();
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(ids.len(), unique.len());
+    }
+
+    #[test]
+    fn reports_empty_values_as_info() {
+        let findings = check("EMPTY", "  ", &HashMap::new());
+        assert_eq!(summary(&findings), vec![("value.empty", Severity::Info)]);
+        assert!(ValidationReport::new("user_EMPTY".into(), "EMPTY".into(), findings).is_valid);
+    }
+
+    #[test]
+    fn reports_undefined_references_as_warnings_and_cycles_as_errors() {
+        let findings = check("GREETING", "hello %WHO%", &HashMap::new());
+        assert_eq!(summary(&findings), vec![("reference.undefined", Severity::Warning)]);
+        assert_eq!(findings[0].suggestion.as_deref(), Some("定义变量 WHO 或移除该引用"));
+
+        let env_map = HashMap::from([("A".to_string(), "%B%".to_string()), ("B".to_string(), "%A%".to_string())]);
+        let findings = check("A", "%B%", &env_map);
+        assert_eq!(summary(&findings), vec![("reference.cycle", Severity::Error)]);
+        assert!(!ValidationReport::new("user_A".into(), "A".into(), findings).is_valid);
+    }
+
+    #[test]
+    fn checks_each_path_entry() {
+        let dir = temp_dir("path-entries");
+        let file = dir.join("file.txt");
+        fs::write(&file, "").unwrap();
+        let missing = dir.join("missing");
+        let dir = dir.to_string_lossy().to_string();
+
+        // 一半有效时只给出警告
+        let value = format!("{};;{}", dir, missing.display());
+        let findings = check("Path", &value, &HashMap::new());
+        assert_eq!(summary(&findings), vec![("path.entries-exist", Severity::Warning)]);
+
+        let value = format!("{};{};{}", dir, file.display(), missing.display());
+        let findings = check("PATH", &value, &HashMap::new());
+        assert_eq!(
+            summary(&findings),
+            vec![
+                ("path.entries-exist", Severity::Warning),
+                ("path.entries-exist", Severity::Warning),
+                ("path.entries-exist", Severity::Error),
+            ]
+        );
+        assert!(findings[0].message.starts_with("路径不是目录"));
+    }
+
+    #[test]
+    fn expands_references_in_path_entries() {
+        let dir = temp_dir("path-expand");
+        let env_map = HashMap::from([("TOOLS".to_string(), dir.to_string_lossy().to_string())]);
+        assert!(check("Path", "%TOOLS%", &env_map).is_empty());
+    }
+
+    #[test]
+    fn checks_path_targets_by_name_suffix() {
+        let dir = temp_dir("path-target");
+        let existing = dir.to_string_lossy().to_string();
+        let missing = dir.join("missing").to_string_lossy().to_string();
+
+        assert!(check("APP_HOME", &existing, &HashMap::new()).is_empty());
+        for name in ["APP_HOME", "CACHE_DIR", "CONFIG_PATH"] {
+            let findings = check(name, &missing, &HashMap::new());
+            assert_eq!(summary(&findings), vec![("path.target-exists", Severity::Error)], "{}", name);
+            assert!(findings[0].message.starts_with(name) && !findings[0].message.contains(&missing));
+        }
+        assert!(check("GREETING", "hello", &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn checks_path_like_values_as_warnings() {
+        let missing = temp_dir("path-like").join("missing").to_string_lossy().to_string();
+        let findings = check("TOOL", &missing, &HashMap::new());
+        assert_eq!(summary(&findings), vec![("path.target-exists", Severity::Warning)]);
+
+        for value in ["Q:\\missing\\tool", "Q:/missing", "\\\\server\\share", "/missing/tool", "~/missing", "%MISSING%\\bin"] {
+            assert!(looks_like_path(value), "{}", value);
+        }
+        // URL、MIME 类型、日期、分数等值不是路径
+        for value in ["https://example.com/api", "text/html", "2024/01/02", "1/2", "a\\b", "C:"] {
+            assert!(!looks_like_path(value), "{}", value);
+            assert!(check("SETTING", value, &HashMap::new()).is_empty(), "{}", value);
+        }
+        assert!(!looks_like_path("%OS%"));
+
+        // 敏感变量不按值推测路径
+        let env_map = HashMap::new();
+        let ctx = ValidationContext::new("API_KEY", "/missing/key", &env_map, ReferenceSyntax::Windows).with_sensitive(true);
+        assert!(validate(&ctx).is_empty());
+    }
+
+    #[test]
+    fn skips_target_checks_for_path_lists() {
+        let dir = temp_dir("path-list");
+        let missing = dir.join("missing").to_string_lossy().to_string();
+        let list = format!("{};{}", dir.to_string_lossy(), missing);
+
+        assert!(!summary(&check("MODULE_PATH", &list, &HashMap::new())).contains(&("path.target-exists", Severity::Error)));
+        assert!(!summary(&check("NODE_PATH", &missing, &HashMap::new())).contains(&("path.target-exists", Severity::Error)));
+    }
+
+    fn rule_ids(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule_id.as_str()).collect()
+    }
+
+    #[test]
+    fn rejects_illegal_names_and_values() {
+        assert_eq!(rule_ids(&check_assignment("", "x", &[])), vec!["name.empty"]);
+        assert_eq!(rule_ids(&check_assignment("A=B", "x", &[])), vec!["name.equals"]);
+        assert_eq!(rule_ids(&check_assignment("A\0", "x", &[])), vec!["name.nul"]);
+        assert_eq!(rule_ids(&check_assignment("A", "x\0y", &[])), vec!["value.nul"]);
+
+        let findings = check_assignment(" PADDED ", "x", &[]);
+        assert_eq!(rule_ids(&findings), vec!["name.whitespace"]);
+        assert_eq!(findings[0].suggestion.as_deref(), Some("使用 'PADDED'"));
+
+        let long_name = "N".repeat(MAX_NAME_LENGTH + 1);
+        assert!(rule_ids(&check_assignment(&long_name, "", &[])).contains(&"name.too-long"));
+        assert!(check_assignment(&"N".repeat(MAX_NAME_LENGTH), "", &[]).is_empty());
+    }
+
+    #[test]
+    fn limits_name_and_value_to_the_variable_length() {
+        // name=value 加结尾 NUL，正好达到上限时允许
+        let value = "v".repeat(MAX_VARIABLE_LENGTH - 3);
+        assert!(check_assignment("A", &value, &[]).is_empty());
+        let value = "v".repeat(MAX_VARIABLE_LENGTH - 2);
+        assert_eq!(rule_ids(&check_assignment("A", &value, &[])), vec!["value.too-long"]);
+    }
+
+    #[test]
+    fn warns_about_non_portable_names_and_case_collisions() {
+        let findings = check_assignment("ProgramFiles(x86)", "C:\\x", &[]);
+        assert_eq!(rule_ids(&findings), vec!["name.portable"]);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(check_assignment("_Valid_1", "x", &[]).is_empty());
+
+        let existing = vec!["Path".to_string(), "JAVA_HOME".to_string()];
+        let findings = check_assignment("PATH", "x", &existing);
+        assert_eq!(rule_ids(&findings), vec!["name.case-collision"]);
+        assert_eq!(findings[0].suggestion.as_deref(), Some("使用已有名称 Path"));
+        assert!(check_assignment("Path", "x", &existing).is_empty());
+    }
+
+    #[test]
+    fn refuses_assignments_with_errors() {
+        assert_eq!(ensure_assignment_allowed("A=B", "x\0", &[]).unwrap_err(), "变量名不能包含 '=': A=B; 变量值不能包含 NUL 字符");
+        let warnings = ensure_assignment_allowed("1ABC", "x", &[]).unwrap();
+        assert_eq!(rule_ids(&warnings), vec!["name.portable"]);
+    }
+
+    #[test]
+    fn validates_all_variables_and_aggregates_counts() {
+        let variables = vec![
+            var("user", "LOOP", "%LOOP%"),
+            var("user", "GREETING", "hello %WHO%"),
+            var("user", "EMPTY", ""),
+            var("user", "PLAIN", "value"),
+        ];
+        let env_map: HashMap<String, String> = variables
+            .iter()
+            .map(|v| (v.name.to_uppercase(), v.value.clone()))
+            .collect();
+        let progress = Mutex::new(Vec::new());
+        let result = validate_all(variables, &env_map, ReferenceSyntax::Windows, 3, |p| {
+            progress.lock().unwrap().push((p.completed, p.total));
+        });
+
+        assert_eq!((result.total, result.valid, result.invalid, result.with_warnings), (4, 3, 1, 1));
+        let ids: Vec<&str> = result.reports.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["user_LOOP", "user_GREETING", "user_EMPTY", "user_PLAIN"]);
+        let validity: Vec<bool> = result.variables.iter().map(|v| v.is_valid).collect();
+        assert_eq!(validity, vec![false, true, true, true]);
+
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+        assert_eq!(progress, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn validates_an_empty_list() {
+        let result = validate_all(Vec::new(), &HashMap::new(), ReferenceSyntax::Windows, 0, |_| {
+            panic!("没有变量时不应推送进度")
+        });
+        assert_eq!((result.total, result.valid, result.invalid), (0, 0, 0));
+        assert!(result.reports.is_empty());
+    }
+}
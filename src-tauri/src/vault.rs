@@ -0,0 +1,223 @@
+// Local encrypted vault for secrets that should not be stored in the registry
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::bundle;
+use crate::commands::get_settings_dir;
+use crate::validation;
+
+// 保险库中的单个密钥，只保存在加密文件和已解锁的内存中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VaultSecret {
+    pub name: String,
+    pub value: String,
+    // 从注册表移入时原来的作用域，移回时默认使用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+}
+
+// 列表中显示的信息，不包含值
+#[derive(Debug, Serialize, Clone)]
+pub struct SecretInfo {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remark: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: u64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+}
+
+impl From<&VaultSecret> for SecretInfo {
+    fn from(secret: &VaultSecret) -> Self {
+        Self {
+            name: secret.name.clone(),
+            scope: secret.scope.clone(),
+            remark: secret.remark.clone(),
+            created_at: secret.created_at,
+            updated_at: secret.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VaultStatus {
+    pub exists: bool,
+    pub unlocked: bool,
+    // 解锁后才知道密钥数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct VaultData {
+    #[serde(default)]
+    secrets: Vec<VaultSecret>,
+}
+
+// 解锁后保留密码，修改后重新加密写回
+struct Unlocked {
+    password: String,
+    data: VaultData,
+}
+
+static UNLOCKED: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+fn vault_path() -> Result<PathBuf, String> {
+    Ok(get_settings_dir()?.join("vault.json"))
+}
+
+// 先写临时文件再替换，避免写入中断损坏保险库
+fn save(unlocked: &Unlocked) -> Result<(), String> {
+    let plaintext = serde_json::to_string(&unlocked.data)
+        .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+    let content = bundle::encrypt(&plaintext, &unlocked.password)?;
+    let path = vault_path()?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, content).map_err(|e| format!("Failed to write vault file: {}", e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to replace vault file: {}", e))
+}
+
+fn with_unlocked<T>(f: impl FnOnce(&mut Unlocked) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = UNLOCKED.lock().map_err(|_| "保险库状态已损坏，请重新启动程序".to_string())?;
+    match guard.as_mut() {
+        Some(unlocked) => f(unlocked),
+        None => Err("保险库未解锁".to_string()),
+    }
+}
+
+pub fn status() -> Result<VaultStatus, String> {
+    let exists = vault_path()?.exists();
+    let guard = UNLOCKED.lock().map_err(|_| "保险库状态已损坏，请重新启动程序".to_string())?;
+    Ok(VaultStatus {
+        exists,
+        unlocked: guard.is_some(),
+        count: guard.as_ref().map(|unlocked| unlocked.data.secrets.len()),
+    })
+}
+
+// 解锁保险库；文件不存在时用该密码创建新的保险库
+pub fn unlock(password: &str) -> Result<VaultStatus, String> {
+    let path = vault_path()?;
+    let unlocked = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read vault file: {}", e))?;
+        let plaintext = bundle::decrypt(&content, password)?;
+        let data = serde_json::from_str(&plaintext)
+            .map_err(|e| format!("Failed to parse vault: {}", e))?;
+        Unlocked { password: password.to_string(), data }
+    } else {
+        let unlocked = Unlocked { password: password.to_string(), data: VaultData::default() };
+        save(&unlocked)?;
+        unlocked
+    };
+
+    *UNLOCKED.lock().map_err(|_| "保险库状态已损坏，请重新启动程序".to_string())? = Some(unlocked);
+    status()
+}
+
+pub fn lock() -> Result<(), String> {
+    *UNLOCKED.lock().map_err(|_| "保险库状态已损坏，请重新启动程序".to_string())? = None;
+    Ok(())
+}
+
+pub fn list() -> Result<Vec<SecretInfo>, String> {
+    with_unlocked(|unlocked| {
+        let mut secrets: Vec<SecretInfo> = unlocked.data.secrets.iter().map(SecretInfo::from).collect();
+        secrets.sort_by_key(|secret| secret.name.to_uppercase());
+        Ok(secrets)
+    })
+}
+
+pub fn get(name: &str) -> Result<VaultSecret, String> {
+    with_unlocked(|unlocked| {
+        unlocked
+            .data
+            .secrets
+            .iter()
+            .find(|secret| secret.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| format!("保险库中没有密钥 '{}'", name))
+    })
+}
+
+// 添加或更新密钥；变量名不区分大小写，与环境变量一致
+pub fn set(name: &str, value: &str, scope: Option<String>, remark: Option<String>, now: u64) -> Result<SecretInfo, String> {
+    validation::ensure_assignment_allowed(name, value, &[])?;
+    with_unlocked(|unlocked| {
+        let secrets = &mut unlocked.data.secrets;
+        let index = match secrets.iter().position(|secret| secret.name.eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                let secret = &mut secrets[index];
+                secret.name = name.to_string();
+                secret.value = value.to_string();
+                secret.updated_at = now;
+                if scope.is_some() {
+                    secret.scope = scope;
+                }
+                if remark.is_some() {
+                    secret.remark = remark;
+                }
+                index
+            }
+            None => {
+                secrets.push(VaultSecret {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    scope,
+                    remark,
+                    created_at: now,
+                    updated_at: now,
+                });
+                secrets.len() - 1
+            }
+        };
+        let info = SecretInfo::from(&unlocked.data.secrets[index]);
+        save(unlocked)?;
+        Ok(info)
+    })
+}
+
+pub fn remove(name: &str) -> Result<VaultSecret, String> {
+    with_unlocked(|unlocked| {
+        let index = unlocked
+            .data
+            .secrets
+            .iter()
+            .position(|secret| secret.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("保险库中没有密钥 '{}'", name))?;
+        let removed = unlocked.data.secrets.remove(index);
+        save(unlocked)?;
+        Ok(removed)
+    })
+}
+
+// 启动命令时注入的变量；names 为空时注入全部密钥
+pub fn launch_environment(names: Option<&[String]>) -> Result<Vec<(String, String)>, String> {
+    with_unlocked(|unlocked| {
+        let secrets = &unlocked.data.secrets;
+        match names {
+            None => Ok(secrets.iter().map(|secret| (secret.name.clone(), secret.value.clone())).collect()),
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    secrets
+                        .iter()
+                        .find(|secret| secret.name.eq_ignore_ascii_case(name))
+                        .map(|secret| (secret.name.clone(), secret.value.clone()))
+                        .ok_or_else(|| format!("保险库中没有密钥 '{}'", name))
+                })
+                .collect(),
+        }
+    })
+}
@@ -0,0 +1,41 @@
+// Background watcher that re-evaluates smart filters when environment variables change
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::commands::{self, EnvironmentVariable};
+
+// 注册表没有便于跨线程等待的变更通知，这里按固定间隔比较快照
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+fn fingerprint(variables: &[EnvironmentVariable]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for var in variables {
+        var.id.hash(&mut hasher);
+        var.value.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+// 启动后台线程：环境变化时推送 environment-changed，并通过 smart-filters-updated 推送最新的智能筛选数量
+pub fn start<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let mut last: Option<u64> = None;
+        loop {
+            if let Ok(variables) = tauri::async_runtime::block_on(commands::load_environment_variables()) {
+                let current = fingerprint(&variables);
+                if last != Some(current) {
+                    if last.is_some() {
+                        let _ = app.emit("environment-changed", ());
+                    }
+                    last = Some(current);
+                    if let Ok(counts) = tauri::async_runtime::block_on(commands::get_smart_filter_counts()) {
+                        let _ = app.emit("smart-filters-updated", counts);
+                    }
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
@@ -1,5 +1,5 @@
-import React, { useEffect } from 'react';
-import { EnvironmentVariable } from '../../utils/types';
+import React, { useEffect, useState } from 'react';
+import { DeleteResult, DependencyNode, EnvironmentVariable, UpdateResult } from '../../utils/types';
 import EnvironmentForm from '../EnvironmentForm/EnvironmentForm';
 import EnvironmentListHeader from './EnvironmentListHeader';
 import EnvironmentListActions from './EnvironmentListActions';
@@ -9,9 +9,12 @@ import './PathAnimation.css';
 import {
   Dialog,
   DialogContent,
+  DialogDescription,
+  DialogFooter,
   DialogHeader,
   DialogTitle,
 } from '../ui/dialog';
+import { Button } from '../ui/button';
 import { useToast } from '../ui/toast';
 import { invoke } from '@tauri-apps/api/core';
 import { useEnvStore } from '../../store/envStore';
@@ -36,6 +39,11 @@ const EnvironmentList: React.FC = () => {
     toggleSection, togglePathVariable
   } = useEnvStore();
 
+  // 因被其他变量引用而未删除的变量，确认后强制删除
+  const [referencedDelete, setReferencedDelete] = useState<{ ids: string[]; referencedBy: DependencyNode[] } | null>(null);
+  // 因被其他变量引用而未重命名的变量，确认后强制重命名
+  const [referencedRename, setReferencedRename] = useState<{ id: string; variable: Omit<EnvironmentVariable, 'id' | 'createdAt' | 'updatedAt' | 'isValid'>; referencedBy: DependencyNode[] } | null>(null);
+
   // 初始加载
   useEffect(() => {
     loadEnvironmentVariables();
@@ -188,11 +196,15 @@ const EnvironmentList: React.FC = () => {
           });
         }
       } else {
-        // 普通变量编辑
-        await safeInvoke('update_environment_variable', {
+        // 普通变量编辑；重命名被引用的变量时先请用户确认
+        const result = await safeInvoke('update_environment_variable', {
           id: editingVariable.id,
           variable: updatedVariable
-        });
+        }) as UpdateResult;
+        if (!result.updated) {
+          setReferencedRename({ id: editingVariable.id, variable: updatedVariable, referencedBy: result.referencedBy ?? [] });
+          return;
+        }
 
         addToast({
           type: 'success',
@@ -214,6 +226,36 @@ const EnvironmentList: React.FC = () => {
     }
   };
 
+  // 确认后重命名被引用的变量，引用它的变量中的 %VAR% 将无法展开
+  const confirmReferencedRename = async () => {
+    if (!referencedRename) return;
+
+    try {
+      await safeInvoke('update_environment_variable', {
+        id: referencedRename.id,
+        variable: referencedRename.variable,
+        force: true
+      });
+      addToast({
+        type: 'success',
+        title: '更新成功',
+        description: `环境变量 "${referencedRename.variable.name}" 已更新`,
+      });
+      setIsEditDialogOpen(false);
+      setEditingVariable(null);
+    } catch (error) {
+      console.error('Failed to rename referenced variable:', error);
+      addToast({
+        type: 'error',
+        title: '更新失败',
+        description: typeof error === 'string' ? error : '无法更新环境变量',
+      });
+    } finally {
+      setReferencedRename(null);
+      loadEnvironmentVariables();
+    }
+  };
+
   const handleEditCancel = () => {
     setIsEditDialogOpen(false);
     setEditingVariable(null);
@@ -227,7 +269,11 @@ const EnvironmentList: React.FC = () => {
     if (!deleteId) return;
 
     try {
-      await safeInvoke('delete_environment_variable', { id: deleteId });
+      const result = await safeInvoke('delete_environment_variable', { id: deleteId }) as DeleteResult;
+      if (!result.deleted) {
+        setReferencedDelete({ ids: [deleteId], referencedBy: result.referencedBy ?? [] });
+        return;
+      }
       addToast({
         type: 'success',
         title: '删除成功',
@@ -239,17 +285,45 @@ const EnvironmentList: React.FC = () => {
       addToast({
         type: 'error',
         title: '删除失败',
-        description: '无法删除环境变量',
+        description: typeof error === 'string' ? error : '无法删除环境变量',
       });
     } finally {
       setDeleteId(null);
     }
   };
 
+  // 确认后删除被引用的变量，引用它们的变量中的 %VAR% 将无法展开
+  const confirmReferencedDelete = async () => {
+    if (!referencedDelete) return;
+
+    try {
+      await Promise.all(
+        referencedDelete.ids.map(id => safeInvoke('delete_environment_variable', { id, force: true }))
+      );
+      addToast({
+        type: 'success',
+        title: '删除成功',
+        description: `已删除 ${referencedDelete.ids.length} 个环境变量`,
+      });
+    } catch (error) {
+      console.error('Failed to delete referenced variables:', error);
+      addToast({
+        type: 'error',
+        title: '删除失败',
+        description: typeof error === 'string' ? error : '无法删除环境变量',
+      });
+    } finally {
+      setReferencedDelete(null);
+      setShowInvalidDialog(false);
+      loadEnvironmentVariables();
+    }
+  };
+
   const handleValidateAll = async () => {
     try {
       setIsValidating(true);
-      const result = await safeInvoke('validate_all_environment_variables') as EnvironmentVariable[];
+      const summary = await safeInvoke('validate_all') as { variables: EnvironmentVariable[] };
+      const result = summary.variables;
 
       const invalidVars = result.filter(v => v.isValid === false);
 
@@ -315,24 +389,35 @@ const EnvironmentList: React.FC = () => {
 
   const handleBatchDeleteInvalid = async (selectedIds: string[]) => {
     try {
-      await Promise.all(
-        selectedIds.map(id => safeInvoke('delete_environment_variable', { id }))
+      const results = await Promise.all(
+        selectedIds.map(id => safeInvoke('delete_environment_variable', { id }) as Promise<DeleteResult>)
       );
 
-      addToast({
-        type: 'success',
-        title: '删除成功',
-        description: `已删除 ${selectedIds.length} 个无效的环境变量`,
-      });
+      // 被引用的变量未删除，汇总后请用户确认
+      const blocked = selectedIds.filter((_, index) => !results[index].deleted);
+      const deletedCount = selectedIds.length - blocked.length;
+      if (deletedCount > 0) {
+        addToast({
+          type: 'success',
+          title: '删除成功',
+          description: `已删除 ${deletedCount} 个无效的环境变量`,
+        });
+      }
 
-      setShowInvalidDialog(false);
+      if (blocked.length > 0) {
+        const referencedBy = new Map<string, DependencyNode>();
+        results.forEach(result => result.referencedBy?.forEach(node => referencedBy.set(node.id, node)));
+        setReferencedDelete({ ids: blocked, referencedBy: Array.from(referencedBy.values()) });
+      } else {
+        setShowInvalidDialog(false);
+      }
       loadEnvironmentVariables();
     } catch (error) {
       console.error('Failed to delete invalid variables:', error);
       addToast({
         type: 'error',
         title: '删除失败',
-        description: '无法删除无效的环境变量',
+        description: typeof error === 'string' ? error : '无法删除无效的环境变量',
       });
     }
   };
@@ -425,6 +510,66 @@ const EnvironmentList: React.FC = () => {
           </DialogContent>
         </Dialog>
 
+        {/* 删除被引用变量的确认对话框 */}
+        <Dialog open={referencedDelete !== null} onOpenChange={open => !open && setReferencedDelete(null)}>
+          <DialogContent className="max-w-md bg-white">
+            <DialogHeader>
+              <DialogTitle>变量正在被引用</DialogTitle>
+              <DialogDescription>
+                {referencedDelete?.ids.length === 1 ? '该变量' : `其中 ${referencedDelete?.ids.length ?? 0} 个变量`}
+                被以下变量引用，删除后这些变量中的引用将无法展开：
+              </DialogDescription>
+            </DialogHeader>
+            <ul className="space-y-1 max-h-60 overflow-y-auto text-sm">
+              {referencedDelete?.referencedBy.map(node => (
+                <li key={node.id} className="px-3 py-2 border rounded">
+                  <span className="font-medium">{node.name}</span>
+                  <span className="text-xs text-gray-400 ml-2">
+                    {node.type === 'system' ? '系统' : '用户'}
+                  </span>
+                </li>
+              ))}
+            </ul>
+            <DialogFooter>
+              <Button variant="outline" onClick={() => setReferencedDelete(null)}>
+                取消
+              </Button>
+              <Button variant="destructive" onClick={confirmReferencedDelete}>
+                仍然删除
+              </Button>
+            </DialogFooter>
+          </DialogContent>
+        </Dialog>
+
+        <Dialog open={referencedRename !== null} onOpenChange={open => !open && setReferencedRename(null)}>
+          <DialogContent className="max-w-md bg-white">
+            <DialogHeader>
+              <DialogTitle>变量正在被引用</DialogTitle>
+              <DialogDescription>
+                该变量被以下变量引用，重命名后这些变量中的引用将无法展开：
+              </DialogDescription>
+            </DialogHeader>
+            <ul className="space-y-1 max-h-60 overflow-y-auto text-sm">
+              {referencedRename?.referencedBy.map(node => (
+                <li key={node.id} className="px-3 py-2 border rounded">
+                  <span className="font-medium">{node.name}</span>
+                  <span className="text-xs text-gray-400 ml-2">
+                    {node.type === 'system' ? '系统' : '用户'}
+                  </span>
+                </li>
+              ))}
+            </ul>
+            <DialogFooter>
+              <Button variant="outline" onClick={() => setReferencedRename(null)}>
+                取消
+              </Button>
+              <Button variant="destructive" onClick={confirmReferencedRename}>
+                仍然重命名
+              </Button>
+            </DialogFooter>
+          </DialogContent>
+        </Dialog>
+
         {/* 无效变量对话框 */}
         <InvalidVariablesDialog
           open={showInvalidDialog}
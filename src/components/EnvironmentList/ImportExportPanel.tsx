@@ -1,7 +1,7 @@
 import React, { useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
-import { EnvironmentVariable } from '../../utils/types';
+import { ImportReport } from '../../utils/types';
 import { useToast } from '../ui/toast';
 
 interface ImportExportPanelProps {
@@ -14,7 +14,7 @@ const ImportExportPanel: React.FC<ImportExportPanelProps> = ({ onImportSuccess }
 
   const handleExport = async () => {
     try {
-      const filePath = await invoke<string>('export_environment_variables');
+      const { path: filePath } = await invoke<{ path: string }>('export_environment_variables');
       console.log('Exported to:', filePath);
       addToast({
          type: 'success',
@@ -43,14 +43,16 @@ const ImportExportPanel: React.FC<ImportExportPanelProps> = ({ onImportSuccess }
       try {
         // 使用文件路径进行导入
         const filePath = (file as any).path || file.name;
-        const variables = await invoke<EnvironmentVariable[]>('import_environment_variables', {
+        const report = await invoke<ImportReport>('import_environment_variables', {
           filePath: filePath
         });
-        console.log('Imported variables:', variables);
+        console.log('Import report:', report);
+        const imported = report.results.filter(r => r.success && r.action !== 'skip').length;
+        const failed = report.results.filter(r => !r.success).length;
         addToast({
-           type: 'success',
-           title: '导入成功',
-           description: `成功导入 ${variables.length} 个环境变量`
+           type: failed > 0 ? 'error' : 'success',
+           title: failed > 0 ? '部分导入失败' : '导入成功',
+           description: `成功导入 ${imported} 个环境变量` + (failed > 0 ? `，${failed} 个失败` : '')
          });
         // 清空文件输入
         if (fileInputRef.current) {
@@ -80,7 +82,7 @@ const ImportExportPanel: React.FC<ImportExportPanelProps> = ({ onImportSuccess }
       <input
         type="file"
         ref={fileInputRef}
-        accept=".json"
+        accept=".json,.env,.reg,.sh,.ps1,.list,.txt"
         onChange={handleImport}
         className="hidden"
       />
@@ -18,6 +18,7 @@ interface SearchQuery {
   valueKeyword?: string;
   remarkKeyword?: string;
   types?: string[];
+  keywordMode?: 'and' | 'or';
 }
 
 interface SearchPanelProps {
@@ -41,6 +42,7 @@ const SearchPanel: React.FC<SearchPanelProps> = ({ onSearchResults, refreshTrigg
         valueKeyword: keyword || undefined,
         remarkKeyword: keyword || undefined,
         types: selectedTypes.length > 0 ? selectedTypes : undefined,
+        keywordMode: 'or',
       };
       
       const results = await invoke<EnvironmentVariable[]>('search_environment_variables', {
@@ -7,4 +7,42 @@ export interface EnvironmentVariable {
   createdAt: string;
   updatedAt: string;
   isValid: boolean;
-}
\ No newline at end of file
+  sensitive?: boolean;
+  expandable?: boolean;
+}
+// 引用关系图中的变量节点
+export interface DependencyNode {
+  id: string;
+  name: string;
+  type?: 'user' | 'system';
+  defined: boolean;
+}
+
+// 删除结果；被其他变量引用且未确认时 deleted 为 false
+export interface DeleteResult {
+  deleted: boolean;
+  referencedBy?: DependencyNode[];
+}
+
+// 更新结果；重命名被引用的变量且未确认时 updated 为 false
+export interface UpdateResult {
+  updated: boolean;
+  variable?: EnvironmentVariable;
+  referencedBy?: DependencyNode[];
+}
+
+export interface ImportResult {
+  name: string;
+  type: 'user' | 'system';
+  status: 'new' | 'identical' | 'conflicting' | 'protected';
+  action: 'add' | 'skip' | 'overwrite' | 'merge' | 'keep-both';
+  target?: string;
+  success: boolean;
+  message?: string;
+}
+
+export interface ImportReport {
+  format: string;
+  diagnostics: { line: number; severity: 'info' | 'warning' | 'error'; message: string }[];
+  results: ImportResult[];
+}